@@ -0,0 +1,47 @@
+/* IPASIR, the Re-entrant Incremental Satisfiability Application Program Interface, by splr */
+#ifndef ipasir_h_INCLUDED
+#define ipasir_h_INCLUDED
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return the name and the version of the solver. */
+const char * ipasir_signature ();
+
+/* Return a new solver, which should be released by 'ipasir_release'. */
+void * ipasir_init ();
+
+/* Release a solver. */
+void ipasir_release (void * solver);
+
+/* Add a literal to the clause being added, or close it with 0. */
+void ipasir_add (void * solver, int32_t lit_or_zero);
+
+/* Add an assumption for the next 'ipasir_solve'. */
+void ipasir_assume (void * solver, int32_t lit);
+
+/* Solve the clauses under the assumptions, which are cleared after this.
+ * Return 10 if satisfiable, 20 if unsatisfiable, or 0 if interrupted. */
+int ipasir_solve (void * solver);
+
+/* Return 'lit' if it is true, '-lit' if false, or 0 if unknown in the last model. */
+int32_t ipasir_val (void * solver, int32_t lit);
+
+/* Return 1 if the assumption 'lit' was used to prove the last unsatisfiability, or 0. */
+int ipasir_failed (void * solver, int32_t lit);
+
+/* Set a callback to stop 'ipasir_solve' by returning a non-zero value, or remove it by NULL. */
+void ipasir_set_terminate (void * solver, void * data, int (*terminate)(void * data));
+
+/* Set a callback to receive learnt clauses up to 'max_length' literals, each of which is
+ * terminated by 0, or remove it by NULL. */
+void ipasir_set_learn (void * solver, void * data, int max_length, void (*learn)(void * data, int32_t * clause));
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
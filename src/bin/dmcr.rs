@@ -1,14 +1,20 @@
 // DIMACS Model Checker in Rust
-#![allow(unused_imports)]
-use splr::config::Config;
-use splr::solver::Solver;
-use splr::traits::{SatSolverIF, ValidatorIF};
-use std::env;
+use splr::types::{read_dimacs_integers, ParseError};
 use std::fs::File;
-use std::io::{stdin, BufRead, BufReader, Result};
+use std::io::{stdin, BufReader};
 use std::path::{Path, PathBuf};
+use std::process;
 use structopt::StructOpt;
 
+/// exit code: the assignment satisfies all the clauses.
+const VALID: i32 = 0;
+/// exit code: the assignment falsifies a clause or contradicts itself.
+const INVALID: i32 = 1;
+/// exit code: the assignment is partial and leaves some clauses undecided.
+const UNDECIDED: i32 = 2;
+/// exit code: no assignment to check, due to a bad input or an IO error.
+const UNCHECKABLE: i32 = 3;
+
 #[derive(StructOpt)]
 #[structopt(name = "dmcr", about = "DIMACS-format Model Checker in Rust")]
 struct TargetOpts {
@@ -20,112 +26,119 @@ struct TargetOpts {
 }
 
 fn main() {
-    let mut from_file = true;
-    let mut found = false;
-    let mut args = TargetOpts::from_args();
-    let cnf = args.problem.to_str().unwrap();
+    let args = TargetOpts::from_args();
+    let cnf = args.problem.to_string_lossy();
     if !args.problem.exists() {
-        println!("{} does not exist.", args.problem.to_str().unwrap(),);
-        return;
+        println!("{} does not exist.", cnf);
+        process::exit(UNCHECKABLE);
     }
-    let mut config = Config::default();
-    config.cnf_filename = args.problem.clone();
-    let mut s = Solver::build(&config).expect("failed to load");
-    if args.assign == None {
-        args.assign = Some(PathBuf::from(format!(
+    let (nv, clauses) = match File::open(&args.problem)
+        .map_err(ParseError::from)
+        .and_then(|f| read_dimacs_integers(BufReader::new(f)))
+    {
+        Ok((Some(nv), clauses)) => (nv, clauses),
+        Ok((None, _)) => {
+            println!("Failed to load {} by {}.", cnf, ParseError::MissingHeader);
+            process::exit(UNCHECKABLE);
+        }
+        Err(e) => {
+            println!("Failed to load {} by {}.", cnf, e);
+            process::exit(UNCHECKABLE);
+        }
+    };
+    let assign = args.assign.clone().unwrap_or_else(|| {
+        PathBuf::from(format!(
             ".ans_{}",
             Path::new(&args.problem)
                 .file_name()
                 .unwrap()
                 .to_string_lossy()
-        )));
-    }
-    if let Some(f) = &args.assign {
-        if let Ok(d) = File::open(f.as_path()) {
-            if let Some(vec) = read_assignment(&mut BufReader::new(d), cnf, &args.assign) {
-                if s.inject_assigmnent(&vec).is_err() {
-                    println!(
-                        "{} seems an unsat problem but no proof.",
-                        args.problem.to_str().unwrap()
-                    );
-                    return;
-                }
-            } else {
-                return;
-            }
-            found = true;
-        }
-    }
-    if !found {
-        if let Some(vec) = read_assignment(&mut BufReader::new(stdin()), cnf, &args.assign) {
-            if s.inject_assigmnent(&vec).is_err() {
-                println!(
-                    "{} seems an unsat problem but no proof.",
-                    args.problem.to_str().unwrap(),
-                );
-                return;
-            }
-            found = true;
-            from_file = false;
-        } else {
-            return;
-        }
-    }
-    if !found {
-        println!("There's no assign file.");
-        return;
-    }
-    match s.validate() {
-        Some(v) => println!(
-            "Invalid assignment set for {} due to {:?}.",
-            args.problem.to_str().unwrap(),
-            v
-        ),
-        None if from_file => println!(
-            "Valid assignment set for {} found in {}.",
-            &args.problem.to_str().unwrap(),
-            &args.assign.unwrap().to_str().unwrap(),
+        ))
+    });
+    let (source, res) = match File::open(&assign) {
+        Ok(f) => (
+            assign.to_string_lossy().to_string(),
+            read_dimacs_integers(BufReader::new(f)),
         ),
-        None => println!(
-            "Valid assignment set for {}.",
-            &args.problem.to_str().unwrap()
+        Err(e) if args.assign.is_some() => {
+            println!("Failed to open {} by {}.", assign.to_string_lossy(), e);
+            process::exit(UNCHECKABLE);
+        }
+        Err(_) => (
+            "STDIN".to_string(),
+            read_dimacs_integers(BufReader::new(stdin())),
         ),
-    }
+    };
+    let model = match res {
+        Ok((_, mut vec)) if !vec.is_empty() => vec.swap_remove(0),
+        Ok(_) => {
+            println!("Can't check {} with {}: no assignment.", cnf, source);
+            process::exit(UNCHECKABLE);
+        }
+        Err(e) => {
+            println!("Can't check {} with {}: {}.", cnf, source, e);
+            process::exit(UNCHECKABLE);
+        }
+    };
+    process::exit(check_assignment(nv, &clauses, &cnf, &source, &model));
 }
 
-fn read_assignment(rs: &mut BufRead, cnf: &str, assign: &Option<PathBuf>) -> Option<Vec<i32>> {
-    let mut buf = String::new();
-    loop {
-        match rs.read_line(&mut buf) {
-            Ok(0) => return Some(Vec::new()),
-            Ok(_) => {
-                if buf.starts_with('c') {
-                    buf.clear();
-                    continue;
-                }
-                if buf.starts_with('s') {
-                    if buf.starts_with("s SATISFIABLE") {
-                        buf.clear();
-                        continue;
-                    } else if buf.starts_with("s UNSATISFIABLE") {
-                        println!("{} seems an unsatisfiable problem. I can't handle it.", cnf);
-                        return None;
-                    } else if let Some(asg) = assign {
-                        println!("{} seems an illegal format file.", asg.to_str().unwrap(),);
-                        return None;
-                    }
-                }
-                let mut v: Vec<i32> = Vec::new();
-                for s in buf.split_whitespace() {
-                    match s.parse::<i32>() {
-                        Ok(0) => break,
-                        Ok(x) => v.push(x),
-                        Err(e) => panic!("{} by {}", e, s),
-                    }
-                }
-                return Some(v);
-            }
-            Err(e) => panic!("{}", e),
+/// checks an assignment against the clauses as they are in the file, and returns the exit code.
+fn check_assignment(
+    nv: usize,
+    clauses: &[Vec<i32>],
+    cnf: &str,
+    source: &str,
+    model: &[i32],
+) -> i32 {
+    let mut assign: Vec<i32> = vec![0; nv + 1];
+    for l in model {
+        let vi = l.unsigned_abs() as usize;
+        if nv < vi {
+            println!(
+                "Invalid assignment set for {} in {}: literal {} is out of range.",
+                cnf, source, l
+            );
+            return INVALID;
+        }
+        if assign[vi] == -l {
+            println!(
+                "Invalid assignment set for {} in {}: literal {} contradicts itself.",
+                cnf, source, l
+            );
+            return INVALID;
         }
+        assign[vi] = *l;
+    }
+    let value = |l: &i32| {
+        assign
+            .get(l.unsigned_abs() as usize)
+            .map_or(0, |a| *a * l.signum())
+    };
+    let mut undecided: Vec<&Vec<i32>> = Vec::new();
+    for c in clauses {
+        if c.iter().any(|l| 0 < value(l)) {
+            continue;
+        }
+        if c.iter().all(|l| value(l) < 0) {
+            println!(
+                "Invalid assignment set for {} in {} due to {:?}.",
+                cnf, source, c
+            );
+            return INVALID;
+        }
+        undecided.push(c);
+    }
+    if let Some(c) = undecided.first() {
+        println!(
+            "Partial assignment set for {} in {}: {} clauses aren't decided, like {:?}.",
+            cnf,
+            source,
+            undecided.len(),
+            c
+        );
+        return UNDECIDED;
     }
+    println!("Valid assignment set for {} found in {}.", cnf, source);
+    VALID
 }
@@ -0,0 +1,51 @@
+// DRAT Certificate Checker in Rust
+use splr::config::Config;
+use splr::solver::Solver;
+use splr::traits::{SatSolverIF, ValidatorIF};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "drcr", about = "DRAT-format Certificate Checker in Rust")]
+struct TargetOpts {
+    /// DRAT proof file in text or binary
+    #[structopt(parse(from_os_str))]
+    #[structopt(short = "p", long = "proof", default_value = "proof.out")]
+    proof: PathBuf,
+    #[structopt(parse(from_os_str))]
+    problem: PathBuf,
+}
+
+fn main() {
+    let args = TargetOpts::from_args();
+    let cnf = args.problem.to_string_lossy();
+    let proof = args.proof.to_string_lossy();
+    let config = Config {
+        cnf_filename: args.problem.clone(),
+        ..Config::default()
+    };
+    let mut s = match Solver::build(&config) {
+        Ok(s) => s,
+        Err(e) => {
+            println!("Failed to load {} by {}.", cnf, e);
+            process::exit(2);
+        }
+    };
+    let fs = match File::open(&args.proof) {
+        Ok(fs) => fs,
+        Err(e) => {
+            println!("Failed to open {} by {}.", proof, e);
+            process::exit(2);
+        }
+    };
+    match s.check_proof(BufReader::new(fs)) {
+        Ok(n) => println!("Valid proof for {} with {} lemmas in {}.", cnf, n, proof),
+        Err(e) => {
+            println!("Invalid proof for {} in {}: {}.", cnf, proof, e);
+            process::exit(1);
+        }
+    }
+}
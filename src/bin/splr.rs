@@ -1,23 +1,48 @@
 // SAT solver for Propositional Logic in Rust
 
 use libc::{clock_gettime, timespec, CLOCK_PROCESS_CPUTIME_ID};
-use splr::clause::CertifiedRecord;
 use splr::config::{Config, VERSION};
-use splr::solver::{Certificate, Solver, SolverResult};
+use splr::counter::ModelCount;
+use splr::eliminator::Eliminator;
+use splr::solver::{Certificate, Solver, SolverException, SolverResult};
 use splr::state::*;
-use splr::traits::SatSolverIF;
+use splr::traits::{CounterIF, EliminatorIF, ObserverIF, PropagatorIF, SatSolverIF, ValidatorIF};
+use splr::types::{read_dimacs_integers, ParseError};
+use std::cell::RefCell;
 use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
 use structopt::StructOpt;
 
 fn main() {
-    let config = Config::from_args();
+    let mut config = Config::from_args();
+    // the competition mode accepts only comment lines as progress messages.
+    config.use_log |= config.use_competition;
+    if !config.extend_filename.as_os_str().is_empty() {
+        if config.reconstruct_filename.as_os_str().is_empty() {
+            println!("Abort: '--extend' needs '--reconstruct' to read the reconstruction stack.");
+            return;
+        }
+        print_extended_model(&config);
+        return;
+    }
+    if config.cnf_filename.as_os_str().is_empty() {
+        println!("Abort: no CNF file is given.");
+        return;
+    }
     if !config.cnf_filename.exists() {
-        println!(
-            "{} does not exist.",
-            config.cnf_filename.file_name().unwrap().to_str().unwrap()
+        notify(
+            &config,
+            &format!(
+                "{} does not exist.",
+                config.cnf_filename.file_name().unwrap().to_str().unwrap()
+            ),
         );
+        if config.use_competition {
+            println!("s UNKNOWN");
+        }
         return;
     }
     let cnf_file = config.cnf_filename.to_string_lossy();
@@ -33,18 +58,556 @@ fn main() {
         println!("Abort: You set a proof filename with '--proof' explicitly, but didn't set '--certify'. It doesn't look good.");
         return;
     }
+    if config.use_binary_proof && !config.use_certification {
+        println!(
+            "Abort: You set '--binary-proof', but didn't set '--certify'. It doesn't look good."
+        );
+        return;
+    }
+    if config.use_frat && !config.use_certification {
+        println!("Abort: You set '--frat', but didn't set '--certify'. It doesn't look good.");
+        return;
+    }
+    if config.use_frat && config.use_binary_proof {
+        println!("Abort: '--frat' can't be used with '--binary-proof'.");
+        return;
+    }
+    if config.preprocess_filename.as_os_str().is_empty()
+        != config.reconstruct_filename.as_os_str().is_empty()
+    {
+        println!("Abort: '--preprocess' and '--reconstruct' should be set together.");
+        return;
+    }
     let proof_file: PathBuf = config.output_dirname.join(&config.proof_filename);
-    let mut s = Solver::build(&config).expect("failed to load");
+    let mut s = match Solver::build(&config) {
+        Ok(s) => s,
+        Err(e) => {
+            notify(&config, &format!("Failed to load {}: {}.", cnf_file, e));
+            if config.use_competition {
+                println!("s UNKNOWN");
+            }
+            return;
+        }
+    };
+    s.state.use_progress = true;
+    if !config.preprocess_filename.as_os_str().is_empty() {
+        save_preprocessed(&config, &mut s);
+        discard_proof(&config, &mut s, &proof_file);
+        return;
+    }
+    let stats_json_file = stats_file(&config, &config.stats_json_filename);
+    let stats_csv_file = stats_file(&config, &config.stats_csv_filename);
+    let snapshots = Rc::new(RefCell::new(Vec::new()));
+    if stats_json_file.is_some() || stats_csv_file.is_some() {
+        s.add_observer(Box::new(StatsRecorder {
+            start: Instant::now(),
+            snapshots: Rc::clone(&snapshots),
+        }));
+    }
+    let handle = s.interrupt_handle();
+    if let Err(e) = ctrlc::set_handler(move || handle.interrupt()) {
+        println!("Failed to set a SIGINT handler by {}.", e);
+    }
+    if config.use_counting {
+        print_count(&config, &mut s);
+        discard_proof(&config, &mut s, &proof_file);
+        return;
+    }
     let res = s.solve();
+    if !matches!(res, Ok(Certificate::UNSAT)) {
+        discard_proof(&config, &mut s, &proof_file);
+    }
+    let mut verified = true;
     match &res {
         Ok(cert) => {
-            save_result(&s, &res, &cnf_file, ans_file);
+            if !config.use_competition {
+                save_result(&s, &res, &cnf_file, ans_file);
+            }
             if config.use_certification && *cert == Certificate::UNSAT {
-                save_proof(&s, &cnf_file, &proof_file);
+                save_proof(&config, &mut s, &proof_file);
+            }
+            if config.use_self_check {
+                verified = self_check(&config, cert, &proof_file);
+            }
+        }
+        _ if config.use_competition => (),
+        Err(SolverException::Interrupted) => {
+            println!("Interrupted: {}.", cnf_file);
+            if let Err(why) = report(&s.state, &mut std::io::stdout()) {
+                println!("Abort: failed to report by {}!", why);
             }
         }
         Err(e) => println!("Failed to execution by {:?}.", e),
     }
+    let result = match &res {
+        Ok(Certificate::SAT(_)) => "SATISFIABLE",
+        Ok(Certificate::UNSAT) => "UNSATISFIABLE",
+        Ok(Certificate::UNSATUnderAssumptions(_)) => "UNKNOWN",
+        Ok(Certificate::Unknown(_)) => "UNKNOWN",
+        Err(SolverException::Interrupted) => "INTERRUPTED",
+        Err(_) => "ERROR",
+    };
+    let snapshots = snapshots.borrow();
+    if let Some(f) = stats_json_file {
+        save_stats(&config, &f, |out| {
+            write_stats_json(&s.state, &snapshots, result, out)
+        });
+    }
+    if let Some(f) = stats_csv_file {
+        save_stats(&config, &f, |out| {
+            write_stats_csv(&s.state, &snapshots, result, out)
+        });
+    }
+    if config.use_competition {
+        std::process::exit(print_competition_result(&s.state, &res, verified));
+    }
+    if !verified {
+        std::process::exit(1);
+    }
+}
+
+/// counts models and prints the count, which follows the model counting competition format
+/// in the competition mode.
+fn print_count(config: &Config, s: &mut Solver) {
+    let cnf_file = config.cnf_filename.to_string_lossy();
+    let projected = !s.state.projection.is_empty();
+    match s.count_models() {
+        Ok(ModelCount::Exact(n)) if config.use_competition => {
+            println!(
+                "s {}",
+                if n.is_zero() {
+                    "UNSATISFIABLE"
+                } else {
+                    "SATISFIABLE"
+                }
+            );
+            println!("c s type {}", if projected { "pmc" } else { "mc" });
+            println!("c s exact arb int {}", n);
+        }
+        Ok(ModelCount::Exact(n)) if projected => println!(
+            "COUNTED: {} has {} models on {} vars.",
+            cnf_file,
+            n,
+            s.state.projection.len()
+        ),
+        Ok(ModelCount::Exact(n)) => println!("COUNTED: {} has {} models.", cnf_file, n),
+        Ok(ModelCount::Unknown(_)) | Err(_) if config.use_competition => println!("s UNKNOWN"),
+        Ok(ModelCount::Unknown(budget)) => println!(
+            "UNKNOWN: {}, by running out of {:?} budget.",
+            cnf_file, budget
+        ),
+        Err(SolverException::Interrupted) => println!("Interrupted: {}.", cnf_file),
+        Err(e) => println!("Failed to execution by {:?}.", e),
+    }
+}
+
+/// prints a message, which should be a comment line in the competition mode.
+fn notify(config: &Config, mes: &str) {
+    if config.use_competition {
+        println!("c {}", mes);
+    } else {
+        println!("{}", mes);
+    }
+}
+
+/// prints `s` and `v` lines to STDOUT in the SAT competition format, and returns the exit code.
+/// A result which failed `--check` is reported as `s UNKNOWN` with exit code 1.
+fn print_competition_result(state: &State, res: &SolverResult, verified: bool) -> i32 {
+    let out = std::io::stdout();
+    let mut buf = BufWriter::new(out.lock());
+    let code = match res {
+        _ if !verified => 1,
+        Ok(Certificate::SAT(_)) => 10,
+        Ok(Certificate::UNSAT) => 20,
+        _ => 0,
+    };
+    let written = (|| {
+        report(state, &mut buf)?;
+        match res {
+            _ if !verified => {
+                buf.write_all(b"c the result failed the self check\n")?;
+                buf.write_all(b"s UNKNOWN\n")?;
+            }
+            Ok(Certificate::SAT(v)) => {
+                buf.write_all(b"s SATISFIABLE\n")?;
+                write_model_lines(v, &mut buf)?;
+            }
+            Ok(Certificate::UNSAT) => buf.write_all(b"s UNSATISFIABLE\n")?,
+            _ => buf.write_all(b"s UNKNOWN\n")?,
+        }
+        buf.flush()
+    })();
+    match written {
+        Err(_) if verified => 0,
+        _ => code,
+    }
+}
+
+/// the maximum width of a `v` line
+const MODEL_LINE_WIDTH: usize = 78;
+
+/// writes `v` lines of a model terminated by `0`.
+fn write_model_lines(model: &[i32], out: &mut dyn Write) -> std::io::Result<()> {
+    let mut line = String::from("v");
+    for x in model.iter().chain(&[0]) {
+        let lit = format!(" {}", x);
+        if MODEL_LINE_WIDTH < line.len() + lit.len() {
+            writeln!(out, "{}", line)?;
+            line = String::from("v");
+        }
+        line.push_str(&lit);
+    }
+    writeln!(out, "{}", line)
+}
+
+/// verifies a model with the original clauses, or a DRAT certification, by a solver loading
+/// the problem again; returns `false` if it is invalid.
+fn self_check(config: &Config, cert: &Certificate, proof_file: &Path) -> bool {
+    let cnf_file = config.cnf_filename.to_string_lossy();
+    let checker_config = Config {
+        use_certification: false,
+        ..config.clone()
+    };
+    let mut checker = match Solver::build(&checker_config) {
+        Ok(s) => s,
+        Err(e) => {
+            notify(
+                config,
+                &format!("Failed to load {} for checking by {}.", cnf_file, e),
+            );
+            return false;
+        }
+    };
+    match cert {
+        Certificate::SAT(model) => {
+            if checker.inject_assigmnent(model).is_err() {
+                notify(
+                    config,
+                    &format!(
+                        "Invalid model: it conflicts with a unit clause in {}.",
+                        cnf_file
+                    ),
+                );
+                return false;
+            }
+            if let Some(c) = checker.validate() {
+                notify(
+                    config,
+                    &format!("Invalid model: it doesn't satisfy a clause {:?}.", c),
+                );
+                return false;
+            }
+            notify(config, "The model was verified with the original clauses.");
+        }
+        Certificate::UNSAT if config.use_frat => {
+            notify(
+                config,
+                "The FRAT certification wasn't checked; '--check' supports DRAT only.",
+            );
+        }
+        Certificate::UNSAT if config.use_certification => {
+            let res = File::open(proof_file)
+                .map_err(|e| e.into())
+                .and_then(|f| checker.check_proof(BufReader::new(f)));
+            match res {
+                Ok(n) => notify(
+                    config,
+                    &format!("The certification was verified with {} lemmas.", n),
+                ),
+                Err(e) => {
+                    notify(
+                        config,
+                        &format!(
+                            "Invalid certification {}: {}.",
+                            proof_file.to_string_lossy(),
+                            e
+                        ),
+                    );
+                    return false;
+                }
+            }
+        }
+        _ => (),
+    }
+    true
+}
+
+/// runs only the preprocessing, then writes the simplified CNF and the reconstruction stack.
+fn save_preprocessed(config: &Config, s: &mut Solver) {
+    let cnf_file = config.cnf_filename.to_string_lossy();
+    let ok = match s.preprocess() {
+        Ok(ok) => ok,
+        Err(e) => {
+            println!("Failed to preprocess {} by {:?}.", cnf_file, e);
+            return;
+        }
+    };
+    let output = config.output_dirname.join(&config.preprocess_filename);
+    if let Err(why) = s.asgs.dump_cnf(&s.cdb, &s.state, &s.vars, &output) {
+        println!(
+            "Abort: failed to save to {} by {}!",
+            output.to_string_lossy(),
+            why
+        );
+        return;
+    }
+    let map = config.output_dirname.join(&config.reconstruct_filename);
+    if let Err(why) = save_reconstruction_stack(s, &map) {
+        println!(
+            "Abort: failed to save to {} by {}!",
+            map.to_string_lossy(),
+            why
+        );
+        return;
+    }
+    if !ok {
+        println!("UNSAT: {}, found by preprocessing.", cnf_file);
+    }
+    println!(
+        "The simplified CNF was saved to {} with {} eliminated vars, and the reconstruction stack to {}.",
+        output.to_string_lossy(),
+        s.state.num_eliminated_vars,
+        map.to_string_lossy(),
+    );
+}
+
+/// writes the reconstruction stack as clauses in the DIMACS format.
+fn save_reconstruction_stack(s: &Solver, output: &Path) -> std::io::Result<()> {
+    let stack = s.elim.reconstruction_stack();
+    let mut buf = BufWriter::new(File::create(output)?);
+    buf.write_all(
+        format!(
+            "c The reconstruction stack generated by splr-{} for {}\n\
+             c The first literal of each clause is on an eliminated var, and a unit clause\n\
+             c holds its default value. They are in the order of elimination.\n\
+             p cnf {} {}\n",
+            VERSION,
+            s.state.target.pathname.as_ref().map_or("--", |p| p),
+            s.state.target.num_of_variables,
+            stack.len(),
+        )
+        .as_bytes(),
+    )?;
+    for c in &stack {
+        for l in c {
+            buf.write_all(format!("{} ", l).as_bytes())?;
+        }
+        buf.write_all(b"0\n")?;
+    }
+    buf.flush()
+}
+
+/// extends a model of a preprocessed CNF to the eliminated vars by the reconstruction stack,
+/// and prints it in `v` lines.
+fn print_extended_model(config: &Config) {
+    let map = config.reconstruct_filename.to_string_lossy();
+    let model_file = config.extend_filename.to_string_lossy();
+    let (nv, stack) = match File::open(&config.reconstruct_filename)
+        .map_err(ParseError::from)
+        .and_then(|f| read_dimacs_integers(BufReader::new(f)))
+    {
+        Ok((Some(nv), stack)) => (nv, stack),
+        Ok((None, _)) => {
+            println!("Abort: {} has no 'p cnf' line.", map);
+            return;
+        }
+        Err(why) => {
+            println!("Abort: failed to read {} by {}!", map, why);
+            return;
+        }
+    };
+    let lits = match File::open(&config.extend_filename)
+        .map_err(ParseError::from)
+        .and_then(|f| read_dimacs_integers(BufReader::new(f)))
+    {
+        Ok((_, mut vec)) if !vec.is_empty() => vec.swap_remove(0),
+        Ok(_) => {
+            println!("Abort: {} has no model.", model_file);
+            return;
+        }
+        Err(why) => {
+            println!("Abort: failed to read {} by {}!", model_file, why);
+            return;
+        }
+    };
+    let mut model = vec![0; nv];
+    for l in lits {
+        if l == 0 || nv < l.unsigned_abs() as usize {
+            println!("Abort: literal {} in {} is out of range.", l, model_file);
+            return;
+        }
+        model[l.unsigned_abs() as usize - 1] = l;
+    }
+    let mut elim = Eliminator::new(nv);
+    elim.extend_reconstruction_stack(&stack);
+    elim.extend_model(&mut model);
+    // vars which are neither assigned nor eliminated are left out.
+    model.retain(|l| *l != 0);
+    let out = std::io::stdout();
+    let mut buf = BufWriter::new(out.lock());
+    if let Err(why) = (|| {
+        buf.write_all(
+            format!(
+                "c A model extended by splr-{} from {} with {}\ns SATISFIABLE\n",
+                VERSION, model_file, map,
+            )
+            .as_bytes(),
+        )?;
+        write_model_lines(&model, &mut buf)?;
+        buf.flush()
+    })() {
+        println!("Abort: failed to print by {}!", why);
+    }
+}
+
+/// collects every progress snapshot with its elapsed time for `--stats-json` and `--stats-csv`.
+struct StatsRecorder {
+    start: Instant,
+    snapshots: Rc<RefCell<Vec<(f64, ProgressRecord)>>>,
+}
+
+impl ObserverIF for StatsRecorder {
+    fn on_progress(&mut self, record: &ProgressRecord) {
+        let e = self.start.elapsed();
+        let time = e.as_secs() as f64 + f64::from(e.subsec_millis()) / 1000.0f64;
+        self.snapshots.borrow_mut().push((time, record.clone()));
+    }
+}
+
+fn stats_file(config: &Config, file: &Path) -> Option<PathBuf> {
+    if file.as_os_str().is_empty() {
+        None
+    } else {
+        Some(config.output_dirname.join(file))
+    }
+}
+
+fn save_stats<F>(config: &Config, output: &Path, write: F)
+where
+    F: FnOnce(&mut dyn Write) -> std::io::Result<()>,
+{
+    let res = File::create(output).and_then(|f| {
+        let mut buf = BufWriter::new(f);
+        write(&mut buf)?;
+        buf.flush()
+    });
+    match res {
+        Ok(_) => notify(
+            config,
+            &format!("The stats were saved to {}.", output.to_string_lossy()),
+        ),
+        Err(why) => notify(
+            config,
+            &format!(
+                "Abort: failed to save to {} by {}!",
+                output.to_string_lossy(),
+                why
+            ),
+        ),
+    }
+}
+
+/// returns the final counters as `(name, value)` pairs.
+fn final_stats(state: &State) -> Vec<(&'static str, String)> {
+    let mut vec: Vec<(&'static str, String)> = Stat::NAMES
+        .iter()
+        .zip(state.stats.iter())
+        .map(|(k, v)| (*k, v.to_string()))
+        .collect();
+    vec.push(("num_solved_vars", state.num_solved_vars.to_string()));
+    vec.push(("num_eliminated_vars", state.num_eliminated_vars.to_string()));
+    vec.push(("restart_k", json_f64(state.restart_thr)));
+    vec.push(("restart_r", json_f64(state.restart_blk)));
+    vec
+}
+
+fn json_f64(x: f64) -> String {
+    if x.is_finite() {
+        format!("{}", x)
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len() + 2);
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            c if (c as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+    buf
+}
+
+fn write_stats_json(
+    state: &State,
+    snapshots: &[(f64, ProgressRecord)],
+    result: &str,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    out.write_all(b"{\n")?;
+    out.write_all(
+        format!(
+            "  \"target\": {},\n  \"num_of_variables\": {},\n  \"num_of_clauses\": {},\n",
+            json_string(state.target.pathname.as_ref().map_or("--", |p| p)),
+            state.target.num_of_variables,
+            state.target.num_of_clauses,
+        )
+        .as_bytes(),
+    )?;
+    out.write_all(b"  \"progress\": [")?;
+    for (i, (time, record)) in snapshots.iter().enumerate() {
+        let mut fields = vec![format!("\"time\": {}", json_f64(*time))];
+        for (k, v) in LogUsizeId::NAMES.iter().zip(record.vali.iter()) {
+            fields.push(format!("\"{}\": {}", k, v));
+        }
+        for (k, v) in LogF64Id::NAMES.iter().zip(record.valf.iter()) {
+            fields.push(format!("\"{}\": {}", k, json_f64(*v)));
+        }
+        let delimiter = if i == 0 { "\n" } else { ",\n" };
+        out.write_all(format!("{}    {{{}}}", delimiter, fields.join(", ")).as_bytes())?;
+    }
+    out.write_all(b"\n  ],\n  \"stats\": {")?;
+    for (i, (k, v)) in final_stats(state).iter().enumerate() {
+        let delimiter = if i == 0 { "\n" } else { ",\n" };
+        out.write_all(format!("{}    \"{}\": {}", delimiter, k, v).as_bytes())?;
+    }
+    out.write_all(
+        format!(
+            "\n  }},\n  \"strategy\": {},\n  \"result\": {}\n}}\n",
+            json_string(state.strategy.to_str()),
+            json_string(result),
+        )
+        .as_bytes(),
+    )
+}
+
+fn write_stats_csv(
+    state: &State,
+    snapshots: &[(f64, ProgressRecord)],
+    result: &str,
+    out: &mut dyn Write,
+) -> std::io::Result<()> {
+    out.write_all(b"record,key,value\n")?;
+    for (i, (time, record)) in snapshots.iter().enumerate() {
+        out.write_all(format!("{},time,{}\n", i, time).as_bytes())?;
+        for (k, v) in LogUsizeId::NAMES.iter().zip(record.vali.iter()) {
+            out.write_all(format!("{},{},{}\n", i, k, v).as_bytes())?;
+        }
+        for (k, v) in LogF64Id::NAMES.iter().zip(record.valf.iter()) {
+            out.write_all(format!("{},{},{}\n", i, k, v).as_bytes())?;
+        }
+    }
+    for (k, v) in final_stats(state) {
+        out.write_all(format!("final,{},{}\n", k, v).as_bytes())?;
+    }
+    out.write_all(format!("final,strategy,\"{}\"\n", state.strategy.to_str()).as_bytes())?;
+    out.write_all(format!("final,result,{}\n", result).as_bytes())
 }
 
 #[allow(dead_code)]
@@ -131,51 +694,76 @@ fn save_result(s: &Solver, res: &SolverResult, input: &str, output: Option<PathB
                 println!("Abort: failed to save by {}!", why);
             }
         }
+        Ok(Certificate::Unknown(budget)) => {
+            println!("UNKNOWN: {}, by running out of {:?} budget.", input, budget);
+            if let Err(why) = (|| {
+                report(&s.state, &mut buf)?;
+                buf.write_all(b"s UNKNOWN\n")
+            })() {
+                println!("Abort: failed to save by {}!", why);
+            }
+        }
+        Ok(Certificate::UNSATUnderAssumptions(v)) => {
+            println!("UNSAT under assumptions: {}.", input);
+            if let Err(why) = (|| {
+                report(&s.state, &mut buf)?;
+                buf.write_all(b"c failed assumptions:")?;
+                for x in v {
+                    write!(buf, " {}", x)?;
+                }
+                // the problem itself may be satisfiable.
+                buf.write_all(b" 0\ns UNKNOWN\n")
+            })() {
+                println!("Abort: failed to save by {}!", why);
+            }
+        }
         Err(e) => println!("Failed to execution by {:?}.", e),
     }
 }
 
-fn save_proof(s: &Solver, input: &str, output: &PathBuf) {
-    let mut buf = match File::create(output) {
-        Ok(out) => BufWriter::new(out),
-        Err(e) => {
-            println!(
-                "Abort: failed to create the proof file {:?} by {}!",
+fn save_proof(config: &Config, s: &mut Solver, output: &Path) {
+    if let Some(Err(why)) = s.cdb.certified.as_mut().map(|p| {
+        p.finalize();
+        p.flush()
+    }) {
+        notify(
+            config,
+            &format!(
+                "Abort: failed to save to {} by {}!",
                 output.to_string_lossy(),
-                e
-            );
-            return;
-        }
-    };
-    if let Err(why) = (|| {
-        buf.write_all(
-            format!("c Proof generated by splr-{} for {}\nc\n", VERSION, input).as_bytes(),
-        )?;
-        buf.write_all(b"s UNSATISFIABLE\n")?;
-        for (f, x) in &s.cdb.certified[1..] {
-            if *f == CertifiedRecord::DELETE {
-                buf.write_all(b"d ")?;
-            }
-            for l in x {
-                buf.write_all(format!("{} ", l).as_bytes())?;
-            }
-            buf.write_all(b"0\n")?;
-        }
-        buf.write_all(b"0\n")
-    })() {
-        println!(
-            "Abort: failed to save to {} by {}!",
-            output.to_string_lossy(),
-            why
+                why
+            ),
         );
         return;
     }
-    println!(
-        "The certification was saved to {}.",
-        output.to_str().unwrap()
+    notify(
+        config,
+        &format!(
+            "The certification was saved to {}.",
+            output.to_str().unwrap()
+        ),
     );
 }
 
+/// removes the proof file written during search, which is meaningless without a refutation.
+fn discard_proof(config: &Config, s: &mut Solver, output: &Path) {
+    if !config.use_certification {
+        return;
+    }
+    // close the file before removing it.
+    s.cdb.certified = None;
+    if let Err(why) = std::fs::remove_file(output) {
+        notify(
+            config,
+            &format!(
+                "Abort: failed to remove {} by {}!",
+                output.to_string_lossy(),
+                why
+            ),
+        );
+    }
+}
+
 fn report(state: &State, out: &mut dyn Write) -> std::io::Result<()> {
     let tm = {
         let mut time = timespec {
@@ -194,7 +782,9 @@ fn report(state: &State, out: &mut dyn Write) -> std::io::Result<()> {
     out.write_all(
         format!(
             "c {:<43}, #var:{:9}, #cls:{:9}\n",
-            state.target.pathname, state.target.num_of_variables, state.target.num_of_clauses,
+            state.target.pathname.as_ref().map_or("--", |p| p),
+            state.target.num_of_variables,
+            state.target.num_of_clauses,
         )
         .as_bytes(),
     )?;
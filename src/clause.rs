@@ -1,4 +1,5 @@
 use crate::eliminator::Eliminator;
+use crate::proof::ProofMemory;
 use crate::propagator::AssignStack;
 use crate::state::{Stat, State};
 use crate::traits::*;
@@ -6,20 +7,21 @@ use crate::types::*;
 use crate::var::Var;
 use std::cmp::Ordering;
 use std::fmt;
+use std::mem::size_of;
 
 const CLA_ACTIVITY_MAX: f64 = 1e240;
 const CLA_ACTIVITY_SCALE1: f64 = 1e-30;
 const CLA_ACTIVITY_SCALE2: f64 = 1e-30;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum CertifiedRecord {
     SENTINEL,
+    /// a clause given in the problem
+    ORIGINAL,
     ADD,
     DELETE,
 }
 
-type DRAT = Vec<(CertifiedRecord, Vec<i32>)>;
-
 impl ClauseIdIF for ClauseId {
     fn to_lit(self) -> Lit {
         (self & 0x7FFF_FFFF) as Lit
@@ -155,7 +157,7 @@ impl Ord for Clause {
     fn cmp(&self, other: &Clause) -> Ordering {
         if self.rank < other.rank {
             Ordering::Less
-        } else if other.rank > self.rank {
+        } else if other.rank < self.rank {
             Ordering::Greater
         } else if self.activity > other.activity {
             Ordering::Less
@@ -201,23 +203,36 @@ pub struct ClauseDB {
     pub watcher: Vec<Vec<Watch>>,
     pub num_active: usize,
     pub num_learnt: usize,
-    pub certified: DRAT,
+    pub certified: Option<Box<dyn ProofSinkIF>>,
+    /// ids in certification, indexed by `ClauseId`
+    pub proof_id: Vec<ProofId>,
+    /// ids of unit clauses in certification, indexed by `VarId`
+    pub proof_unit: Vec<ProofId>,
+    /// antecedents of the clause made by the next `attach`
+    pub proof_hints: Vec<ProofId>,
+    next_proof_id: ProofId,
 }
 
 impl ClauseDBIF for ClauseDB {
     fn new(nv: usize, nc: usize, certify: bool) -> ClauseDB {
         let mut clause = Vec::with_capacity(1 + nc);
         clause.push(Clause::default());
+        let mut proof_id = Vec::new();
+        if certify {
+            proof_id.reserve(1 + nc);
+        }
+        proof_id.push(0);
         let mut watcher = Vec::with_capacity(2 * (nv + 1));
         let mut touched = Vec::with_capacity(2 * (nv + 1));
         for _ in 0..2 * (nv + 1) {
             watcher.push(Vec::new());
             touched.push(false);
         }
-        let mut certified = Vec::new();
-        if certify {
-            certified.push((CertifiedRecord::SENTINEL, Vec::new()));
-        }
+        let certified: Option<Box<dyn ProofSinkIF>> = if certify {
+            Some(Box::new(ProofMemory::default()))
+        } else {
+            None
+        };
         ClauseDB {
             clause,
             touched,
@@ -225,8 +240,19 @@ impl ClauseDBIF for ClauseDB {
             num_active: 0,
             num_learnt: 0,
             certified,
+            proof_id,
+            proof_unit: vec![0; nv + 1],
+            proof_hints: Vec::new(),
+            next_proof_id: 1,
         }
     }
+    fn new_var(&mut self) {
+        for _ in 0..2 {
+            self.watcher.push(Vec::new());
+            self.touched.push(false);
+        }
+        self.proof_unit.push(0);
+    }
     fn garbage_collect(&mut self) {
         // debug_assert!(self.check_liveness1());
         let ClauseDB {
@@ -234,6 +260,7 @@ impl ClauseDBIF for ClauseDB {
             ref mut clause,
             ref mut touched,
             ref mut certified,
+            ref proof_id,
             ..
         } = self;
         debug_assert_eq!(NULL_LIT.negate(), 1);
@@ -261,9 +288,13 @@ impl ClauseDBIF for ClauseDB {
                     if c.is(Flag::LEARNT) {
                         self.num_learnt -= 1;
                     }
-                    if !certified.is_empty() {
-                        let temp = c.lits.iter().map(|l| l.to_i32()).collect::<Vec<i32>>();
-                        certified.push((CertifiedRecord::ADD, temp));
+                    if let Some(p) = certified {
+                        p.record(
+                            CertifiedRecord::DELETE,
+                            proof_id[cid as usize],
+                            &c.lits,
+                            &[],
+                        );
                     }
                     c.lits.clear();
                 }
@@ -298,12 +329,14 @@ impl ClauseDBIF for ClauseDB {
             }
             c.rank = rank;
             c.activity = 0.0;
+            self.proof_id[cid as usize] = 0;
         } else {
             let mut lits = Vec::with_capacity(v.len());
             for l in v {
                 lits.push(*l);
             }
             cid = self.clause.len() as ClauseId;
+            self.proof_id.push(0);
             let c = Clause {
                 flags: Flag::empty(),
                 lits,
@@ -371,10 +404,8 @@ impl ClauseDBIF for ClauseDB {
     // Note: set lbd to 0 if you want to add the clause to Permanent.
     fn attach(&mut self, state: &mut State, vars: &mut [Var], lbd: usize) -> ClauseId {
         let v = &mut state.new_learnt;
-        if !self.certified.is_empty() {
-            let temp = v.iter().map(|l| l.to_i32()).collect::<Vec<i32>>();
-            self.certified.push((CertifiedRecord::ADD, temp));
-        }
+        let hints = std::mem::take(&mut self.proof_hints);
+        let id = self.certificate_add(v, &hints);
         debug_assert!(1 < v.len());
         let mut i_max = 0;
         let mut lv_max = 0;
@@ -390,6 +421,7 @@ impl ClauseDBIF for ClauseDB {
         v.swap(1, i_max);
         let learnt = 0 < lbd && 2 < v.len() && (!state.use_chan_seok || state.co_lbd_bound < lbd);
         let cid = self.new_clause(&v, lbd, learnt);
+        self.proof_id[cid as usize] = id;
         let c = &mut self.clause[cid as usize];
         c.activity = state.var_inc;
         cid
@@ -440,6 +472,9 @@ impl ClauseDBIF for ClauseDB {
         }
         state.stats[Stat::Reduction] += 1;
         self.garbage_collect();
+        for o in &mut state.observers {
+            o.on_reduce();
+        }
     }
     fn simplify(
         &mut self,
@@ -449,6 +484,7 @@ impl ClauseDBIF for ClauseDB {
         vars: &mut [Var],
     ) -> MaybeInconsistent {
         debug_assert_eq!(asgs.level(), 0);
+        self.certificate_units(asgs, vars);
         // we can reset all the reasons because decision level is zero.
         for v in &mut vars[1..] {
             v.reason = NULL_CLAUSE;
@@ -461,9 +497,10 @@ impl ClauseDBIF for ClauseDB {
             let na = asgs.len();
             elim.eliminate(asgs, self, state, vars)?;
             self.eliminate_satisfied_clauses(elim, vars, true);
-            if na == asgs.len()
-                && (!elim.is_running()
-                    || (0 == elim.clause_queue_len() && 0 == elim.var_queue_len()))
+            if state.is_interrupted()
+                || na == asgs.len()
+                    && (!elim.is_running()
+                        || (0 == elim.clause_queue_len() && 0 == elim.var_queue_len()))
             {
                 break;
             }
@@ -475,6 +512,9 @@ impl ClauseDBIF for ClauseDB {
             self.reset_lbd(vars, &mut state.lbd_temp);
             elim.stop(self, vars);
         }
+        for o in &mut state.observers {
+            o.on_simplify();
+        }
         if self.check_size(state).is_err() {
             Err(SolverError::Inconsistent)
         } else {
@@ -490,18 +530,64 @@ impl ClauseDBIF for ClauseDB {
         }
         self.garbage_collect();
     }
-    fn certificate_add(&mut self, vec: &[Lit]) {
-        if !self.certified.is_empty() {
-            let temp = vec.iter().map(|l| l.to_i32()).collect::<Vec<i32>>();
-            self.certified.push((CertifiedRecord::ADD, temp));
+    fn certificate_original(&mut self, vec: &[Lit]) -> ProofId {
+        if let Some(p) = &mut self.certified {
+            let id = self.next_proof_id;
+            self.next_proof_id += 1;
+            p.record(CertifiedRecord::ORIGINAL, id, vec, &[]);
+            id
+        } else {
+            0
         }
     }
-    fn certificate_delete(&mut self, vec: &[Lit]) {
-        if !self.certified.is_empty() {
-            let temp = vec.iter().map(|l| l.to_i32()).collect::<Vec<i32>>();
-            self.certified.push((CertifiedRecord::DELETE, temp));
+    fn certificate_add(&mut self, vec: &[Lit], hints: &[ProofId]) -> ProofId {
+        if let Some(p) = &mut self.certified {
+            let id = self.next_proof_id;
+            self.next_proof_id += 1;
+            p.record(CertifiedRecord::ADD, id, vec, hints);
+            id
+        } else {
+            0
         }
     }
+    fn certificate_delete(&mut self, id: ProofId, vec: &[Lit]) {
+        if let Some(p) = &mut self.certified {
+            p.record(CertifiedRecord::DELETE, id, vec, &[]);
+        }
+    }
+    fn certificate_units(&mut self, asgs: &AssignStack, vars: &[Var]) {
+        if !self.use_hints() {
+            return;
+        }
+        let n = if asgs.level() == 0 {
+            asgs.len()
+        } else {
+            asgs.num_at(0)
+        };
+        // the vars before `l` on the trail have got their units already.
+        for l in &asgs.trail[..n] {
+            let vi = l.vi();
+            let cid = vars[vi].reason;
+            if self.proof_unit[vi] != 0 || cid == NULL_CLAUSE {
+                continue;
+            }
+            let lits = &self.clause[cid as usize].lits;
+            let mut hints = lits
+                .iter()
+                .filter(|q| q.vi() != vi)
+                .map(|q| self.proof_unit[q.vi()])
+                .collect::<Vec<ProofId>>();
+            if hints.contains(&0) {
+                hints.clear();
+            } else {
+                hints.push(self.proof_id[cid as usize]);
+            }
+            self.proof_unit[vi] = self.certificate_add(&[*l], &hints);
+        }
+    }
+    fn use_hints(&self) -> bool {
+        matches!(&self.certified, Some(p) if p.use_hints())
+    }
     fn eliminate_satisfied_clauses(
         &mut self,
         elim: &mut Eliminator,
@@ -532,6 +618,22 @@ impl ClauseDBIF for ClauseDB {
             Err(SolverError::Inconsistent)
         }
     }
+    fn memory_usage(&self) -> usize {
+        let clauses = self
+            .clause
+            .iter()
+            .map(|c| c.lits.capacity() * size_of::<Lit>())
+            .sum::<usize>();
+        let watchers = self
+            .watcher
+            .iter()
+            .map(|w| w.capacity() * size_of::<Watch>())
+            .sum::<usize>();
+        self.clause.capacity() * size_of::<Clause>()
+            + clauses
+            + self.watcher.capacity() * size_of::<Vec<Watch>>()
+            + watchers
+    }
 }
 
 /*
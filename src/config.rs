@@ -35,7 +35,7 @@ pub struct Config {
     #[structopt(long = "rs", default_value = "50")]
     pub restart_step: usize,
     /// a DIMACS format CNF file
-    #[structopt(parse(from_os_str))]
+    #[structopt(default_value = "", parse(from_os_str))]
     pub cnf_filename: PathBuf,
     /// output directory
     #[structopt(long = "--dir", short = "o", default_value = ".", parse(from_os_str))]
@@ -51,6 +51,21 @@ pub struct Config {
         parse(from_os_str)
     )]
     pub proof_filename: PathBuf,
+    /// filename to export progress snapshots and final stats in JSON
+    #[structopt(long = "stats-json", default_value = "", parse(from_os_str))]
+    pub stats_json_filename: PathBuf,
+    /// filename to export progress snapshots and final stats in CSV
+    #[structopt(long = "stats-csv", default_value = "", parse(from_os_str))]
+    pub stats_csv_filename: PathBuf,
+    /// filename to write the CNF simplified by preprocessing, without search
+    #[structopt(long = "preprocess", default_value = "", parse(from_os_str))]
+    pub preprocess_filename: PathBuf,
+    /// filename of the reconstruction stack written by '--preprocess' and read by '--extend'
+    #[structopt(long = "reconstruct", default_value = "", parse(from_os_str))]
+    pub reconstruct_filename: PathBuf,
+    /// filename of a model of a preprocessed CNF to extend to the eliminated vars
+    #[structopt(long = "extend", default_value = "", parse(from_os_str))]
+    pub extend_filename: PathBuf,
     /// Uses Glucose format for progress report
     #[structopt(long = "--log", short = "l")]
     pub use_log: bool,
@@ -72,9 +87,39 @@ pub struct Config {
     /// Writes a DRAT UNSAT certification file
     #[structopt(long = "certify", short = "c")]
     pub use_certification: bool,
+    /// Writes the certification in the binary DRAT format
+    #[structopt(long = "binary-proof")]
+    pub use_binary_proof: bool,
+    /// Writes the certification in the FRAT format with clause ids and antecedents
+    #[structopt(long = "frat")]
+    pub use_frat: bool,
+    /// Verifies a model with the original clauses, or a DRAT certification, after solving
+    #[structopt(long = "check")]
+    pub use_self_check: bool,
+    /// Prints the result in the SAT competition format and exits with 10, 20 or 0 (1 if '--check' fails)
+    #[structopt(long = "competition")]
+    pub use_competition: bool,
+    /// Counts models, on the vars in 'c ind' lines if any, instead of solving
+    #[structopt(long = "count")]
+    pub use_counting: bool,
     /// CPU time limit in sec. (0 for no limit)
     #[structopt(long = "to", default_value = "0")]
     pub timeout: f64,
+    /// #conflicts per solve (0 for no limit)
+    #[structopt(long = "max-conflicts", default_value = "0")]
+    pub conflict_budget: usize,
+    /// #propagations per solve (0 for no limit)
+    #[structopt(long = "max-propagations", default_value = "0")]
+    pub propagation_budget: usize,
+    /// #decisions per solve (0 for no limit)
+    #[structopt(long = "max-decisions", default_value = "0")]
+    pub decision_budget: usize,
+    /// memory limit in MB for clauses, watchers and occur lists (0 for no limit)
+    #[structopt(long = "max-memory", default_value = "0")]
+    pub memory_budget: usize,
+    /// Tolerates mismatches between the CNF header and clauses
+    #[structopt(long = "lenient")]
+    pub use_lenient_parser: bool,
 }
 
 impl Default for Config {
@@ -92,6 +137,11 @@ impl Default for Config {
             output_dirname: PathBuf::from("."),
             result_filename: PathBuf::new(),
             proof_filename: PathBuf::from("proof.out"),
+            stats_json_filename: PathBuf::new(),
+            stats_csv_filename: PathBuf::new(),
+            preprocess_filename: PathBuf::new(),
+            reconstruct_filename: PathBuf::new(),
+            extend_filename: PathBuf::new(),
             use_log: false,
             without_elim: false,
             without_adaptive_restart: false,
@@ -99,7 +149,17 @@ impl Default for Config {
             without_deep_search: false,
             with_learnt_minimization: false,
             use_certification: false,
+            use_binary_proof: false,
+            use_frat: false,
+            use_self_check: false,
+            use_competition: false,
+            use_counting: false,
             timeout: 0.0,
+            conflict_budget: 0,
+            propagation_budget: 0,
+            decision_budget: 0,
+            memory_budget: 0,
+            use_lenient_parser: false,
         }
     }
 }
@@ -0,0 +1,495 @@
+use crate::clause::ClauseDB;
+use crate::propagator::AssignStack;
+use crate::solver::{Solver, SolverException};
+use crate::state::{Budget, Stat, State};
+use crate::traits::{ClauseDBIF, CounterIF, FlagIF, LitIF, PropagatorIF, StateIF, VarDBIF};
+use crate::types::{ClauseId, Flag, Lit, VarId, BOTTOM, FALSE, NULL_CLAUSE, TRUE};
+use crate::var::Var;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+use std::ops::{AddAssign, MulAssign, ShlAssign};
+
+/// the size of the cache in bytes, beyond which it's cleared
+const CACHE_LIMIT: usize = 1 << 28;
+
+/// The return type of `CounterIF::count_models`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelCount {
+    /// the number of models
+    Exact(Count),
+    /// stopped by running out of a budget
+    Unknown(Budget),
+}
+
+/// An arbitrary-precision natural number for model counts.
+///
+/// ```
+/// use splr::counter::Count;
+///
+/// let mut n = Count::from(3);
+/// n <<= 100;
+/// n *= &Count::from(5);
+/// n += &Count::from(1);
+/// assert_eq!(n.to_string(), "19014759003423441022450548080641");
+///```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Count {
+    /// digits in base 2^32 from the lowest, without leading zeros
+    digits: Vec<u32>,
+}
+
+impl Count {
+    pub fn zero() -> Count {
+        Count { digits: Vec::new() }
+    }
+    pub fn one() -> Count {
+        Count::from(1)
+    }
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+    fn normalize(&mut self) {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+    }
+}
+
+impl From<u64> for Count {
+    fn from(n: u64) -> Count {
+        let mut c = Count {
+            digits: vec![n as u32, (n >> 32) as u32],
+        };
+        c.normalize();
+        c
+    }
+}
+
+impl AddAssign<&Count> for Count {
+    fn add_assign(&mut self, other: &Count) {
+        if self.digits.len() < other.digits.len() {
+            self.digits.resize(other.digits.len(), 0);
+        }
+        let mut carry = 0u64;
+        for (i, d) in self.digits.iter_mut().enumerate() {
+            let sum = *d as u64 + other.digits.get(i).copied().unwrap_or(0) as u64 + carry;
+            *d = sum as u32;
+            carry = sum >> 32;
+            if carry == 0 && other.digits.len() <= i {
+                break;
+            }
+        }
+        if 0 < carry {
+            self.digits.push(carry as u32);
+        }
+    }
+}
+
+impl MulAssign<&Count> for Count {
+    fn mul_assign(&mut self, other: &Count) {
+        if self.is_zero() || other.is_zero() {
+            self.digits.clear();
+            return;
+        }
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+        for (i, a) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.digits.iter().enumerate() {
+                let t = *a as u64 * *b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            digits[i + other.digits.len()] = carry as u32;
+        }
+        self.digits = digits;
+        self.normalize();
+    }
+}
+
+impl ShlAssign<usize> for Count {
+    fn shl_assign(&mut self, n: usize) {
+        if self.is_zero() {
+            return;
+        }
+        let (words, bits) = (n / 32, n % 32);
+        if 0 < bits {
+            let mut carry = 0u32;
+            for d in self.digits.iter_mut() {
+                let t = *d;
+                *d = (t << bits) | carry;
+                carry = t >> (32 - bits);
+            }
+            if 0 < carry {
+                self.digits.push(carry);
+            }
+        }
+        self.digits.splice(0..0, vec![0; words]);
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // convert to digits in base 10^9 from the lowest.
+        const BASE: u64 = 1_000_000_000;
+        let mut rest = self.digits.clone();
+        let mut chunks: Vec<u32> = Vec::new();
+        while !rest.is_empty() {
+            let mut r = 0u64;
+            for d in rest.iter_mut().rev() {
+                let t = (r << 32) | *d as u64;
+                *d = (t / BASE) as u32;
+                r = t % BASE;
+            }
+            chunks.push(r as u32);
+            while rest.last() == Some(&0) {
+                rest.pop();
+            }
+        }
+        let mut iter = chunks.iter().rev();
+        if let Some(top) = iter.next() {
+            write!(f, "{}", top)?;
+        }
+        for c in iter {
+            write!(f, "{:09}", c)?;
+        }
+        Ok(())
+    }
+}
+
+impl CounterIF for Solver {
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::{CounterIF, SatSolverIF};
+    /// use splr::config::Config;
+    /// use splr::counter::{Count, ModelCount};
+    /// use splr::solver::Solver;
+    ///
+    /// let cnf = "c ind 1 3 0\np cnf 3 2\n1 2 0\n-1 3 0\n";
+    /// let mut s = Solver::from_reader(&Config::default(), cnf.as_bytes()).unwrap();
+    /// // the models projected on 1 and 3 are {1, 3}, {-1, 3} and {-1, -3}.
+    /// assert_eq!(s.count_models().unwrap(), ModelCount::Exact(Count::from(3)));
+    /// s.state.projection.clear();
+    /// assert_eq!(s.count_models().unwrap(), ModelCount::Exact(Count::from(4)));
+    ///```
+    fn count_models(&mut self) -> Result<ModelCount, SolverException> {
+        // eliminated vars have no clauses to count their values.
+        for vi in 1..self.vars.len() {
+            self.restore_eliminated_var(vi);
+        }
+        let Solver {
+            ref mut asgs,
+            ref mut cdb,
+            ref mut state,
+            ref mut vars,
+            ..
+        } = self;
+        debug_assert!(asgs.is_zero());
+        if !state.ok || asgs.propagate(cdb, state, vars) != NULL_CLAUSE {
+            return Ok(ModelCount::Exact(Count::zero()));
+        }
+        state.reset_budget();
+        let mut projected = vec![state.projection.is_empty(); vars.len()];
+        for vi in &state.projection {
+            projected[*vi] = true;
+        }
+        let mut counter = Counter {
+            cache: HashMap::new(),
+            cache_size: 0,
+            memory_usage: cdb.memory_usage() + vars.memory_usage(),
+            projected,
+            index: vec![0; vars.len()],
+            occurs: vec![0; vars.len()],
+            position: vec![NO_COMPONENT; vars.len()],
+        };
+        let vs = (1..vars.len()).collect::<Vec<VarId>>();
+        let cs = (1..cdb.clause.len())
+            .filter(|i| {
+                let c = &cdb.clause[*i];
+                !c.is(Flag::DEAD) && !c.is(Flag::LEARNT)
+            })
+            .map(|i| i as ClauseId)
+            .collect::<Vec<ClauseId>>();
+        let res = counter.count(asgs, cdb, state, vars, &vs, &cs);
+        asgs.cancel_until(vars, 0);
+        if res.is_err() {
+            state.interrupt.clear();
+        }
+        res
+    }
+}
+
+/// a mark of vars in no component in `Counter::position`
+const NO_COMPONENT: usize = usize::MAX;
+
+/// a connected component: unassigned vars and unsatisfied clauses
+type Component = (Vec<VarId>, Vec<ClauseId>);
+
+/// A suspended step of `Counter::count`, which receives the count of the step above it.
+enum Task {
+    /// multiply the counts of the components
+    Product {
+        total: Count,
+        pending: Vec<Component>,
+    },
+    /// branch on `vi` in a component, then cache the sum of the counts under `key`
+    Branch {
+        key: Vec<usize>,
+        component: Component,
+        vi: VarId,
+        /// the number of the phases tried
+        phase: usize,
+        level: usize,
+        exists_only: bool,
+        total: Count,
+    },
+}
+
+/// DPLL-style model counting on the residual problem under the current assignment.
+struct Counter {
+    /// the counts of components keyed by their vars, `0`, and their clauses
+    cache: HashMap<Vec<usize>, Count>,
+    /// the estimated size of `cache` in bytes
+    cache_size: usize,
+    /// the memory used by the clauses and vars, which don't change in counting
+    memory_usage: usize,
+    /// `projected[vi]` is `true` if `vi` is counted.
+    projected: Vec<bool>,
+    /// a buffer for union-find over vars
+    index: Vec<usize>,
+    /// a buffer for the number of occurrences of vars in a component
+    occurs: Vec<usize>,
+    /// a buffer for the component of a root var in union-find
+    position: Vec<usize>,
+}
+
+impl Counter {
+    /// returns the number of models on vars `vs` which satisfy clauses `cs` under the
+    /// current assignment, as the product of the counts of connected components.
+    /// It branches with an explicit stack of `Task`s instead of recursion, which would be
+    /// as deep as the number of vars.
+    fn count(
+        &mut self,
+        asgs: &mut AssignStack,
+        cdb: &mut ClauseDB,
+        state: &mut State,
+        vars: &mut [Var],
+        vs: &[VarId],
+        cs: &[ClauseId],
+    ) -> Result<ModelCount, SolverException> {
+        let mut stack = vec![self.product(cdb, vars, vs, cs)];
+        // the count returned from the task popped last
+        let mut ret: Option<Count> = None;
+        while let Some(task) = stack.last_mut() {
+            match task {
+                Task::Product { total, pending } => {
+                    if let Some(n) = ret.take() {
+                        if n.is_zero() {
+                            pending.clear();
+                        }
+                        *total *= &n;
+                    }
+                    let (cvs, ccs) = match pending.pop() {
+                        Some(component) => component,
+                        None => {
+                            ret = Some(std::mem::take(total));
+                            stack.pop();
+                            continue;
+                        }
+                    };
+                    let mut key = cvs.clone();
+                    key.push(0);
+                    key.extend(ccs.iter().map(|c| *c as usize));
+                    if let Some(n) = self.cache.get(&key) {
+                        ret = Some(n.clone());
+                        continue;
+                    }
+                    if state.is_interrupted() {
+                        return Err(SolverException::Interrupted);
+                    }
+                    if state.is_over_budget() {
+                        return Ok(ModelCount::Unknown(state.exhausted.clone().unwrap()));
+                    }
+                    // A component without counted vars has one model at most.
+                    let exists_only = !cvs.iter().any(|vi| self.projected[*vi]);
+                    let vi = self.select_var(cdb, vars, &cvs, &ccs, exists_only);
+                    stack.push(Task::Branch {
+                        key,
+                        component: (cvs, ccs),
+                        vi,
+                        phase: 0,
+                        level: asgs.level(),
+                        exists_only,
+                        total: Count::zero(),
+                    });
+                }
+                Task::Branch {
+                    key,
+                    component: (cvs, ccs),
+                    vi,
+                    phase,
+                    level,
+                    exists_only,
+                    total,
+                } => {
+                    if let Some(n) = ret.take() {
+                        asgs.cancel_until(vars, *level);
+                        *total += &n;
+                        *phase += 1;
+                        if *exists_only && !total.is_zero() {
+                            *phase = 2;
+                        }
+                    }
+                    if *phase == 2 {
+                        let n = std::mem::take(total);
+                        let size = size_of::<(Vec<usize>, Count)>()
+                            + key.capacity() * size_of::<usize>()
+                            + n.digits.capacity() * size_of::<u32>();
+                        if CACHE_LIMIT <= self.cache_size + size {
+                            self.cache.clear();
+                            self.cache_size = 0;
+                        }
+                        self.cache_size += size;
+                        self.cache.insert(std::mem::take(key), n.clone());
+                        if 0 < state.budget_memory
+                            && state.budget_memory < self.memory_usage + self.cache_size
+                        {
+                            state.exhausted = Some(Budget::Memory);
+                            return Ok(ModelCount::Unknown(Budget::Memory));
+                        }
+                        ret = Some(n);
+                        stack.pop();
+                        continue;
+                    }
+                    asgs.uncheck_assume(vars, Lit::from_var(*vi, [TRUE, FALSE][*phase]));
+                    state.stats[Stat::Decision] += 1;
+                    state.stats[Stat::Propagation] += 1;
+                    if asgs.propagate(cdb, state, vars) == NULL_CLAUSE {
+                        let product = self.product(cdb, vars, cvs, ccs);
+                        stack.push(product);
+                    } else {
+                        state.stats[Stat::Conflict] += 1;
+                        ret = Some(Count::zero());
+                    }
+                }
+            }
+        }
+        Ok(ModelCount::Exact(ret.unwrap()))
+    }
+    /// returns a task to multiply the counts of the components in `vs` and `cs`.
+    fn product(&mut self, cdb: &ClauseDB, vars: &[Var], vs: &[VarId], cs: &[ClauseId]) -> Task {
+        let (pending, num_free) = self.split(cdb, vars, vs, cs);
+        let mut total = Count::one();
+        total <<= num_free;
+        Task::Product { total, pending }
+    }
+    /// returns the var occurring most in the component, preferring counted vars.
+    fn select_var(
+        &mut self,
+        cdb: &ClauseDB,
+        vars: &[Var],
+        vs: &[VarId],
+        cs: &[ClauseId],
+        exists_only: bool,
+    ) -> VarId {
+        for cid in cs {
+            for l in &cdb.clause[*cid as usize].lits {
+                if vars[l.vi()].assign == BOTTOM {
+                    self.occurs[l.vi()] += 1;
+                }
+            }
+        }
+        let vi = *vs
+            .iter()
+            .filter(|vi| exists_only || self.projected[**vi])
+            .max_by_key(|vi| (self.occurs[**vi], std::cmp::Reverse(**vi)))
+            .unwrap();
+        for vi in vs {
+            self.occurs[*vi] = 0;
+        }
+        vi
+    }
+    /// splits the unassigned vars in `vs` and the unsatisfied clauses in `cs` into connected
+    /// components, and returns them with the number of counted vars in no clause.
+    fn split(
+        &mut self,
+        cdb: &ClauseDB,
+        vars: &[Var],
+        vs: &[VarId],
+        cs: &[ClauseId],
+    ) -> (Vec<Component>, usize) {
+        let unassigned = |vi: VarId| vars[vi].assign == BOTTOM;
+        // `index[vi]` is the parent of `vi` in union-find.
+        for vi in vs {
+            self.index[*vi] = *vi;
+        }
+        let mut live = Vec::new();
+        for cid in cs {
+            let c = &cdb.clause[*cid as usize];
+            if c.lits.iter().any(|l| vars.assigned(*l) == TRUE) {
+                continue;
+            }
+            let mut root = 0;
+            for l in &c.lits {
+                let vi = l.vi();
+                if !unassigned(vi) {
+                    continue;
+                }
+                let r = find(&mut self.index, vi);
+                if root == 0 {
+                    root = r;
+                } else if r != root {
+                    self.index[r] = root;
+                }
+            }
+            if root != 0 {
+                live.push(*cid);
+            }
+        }
+        // `position[r]` is the index of the component whose root is `r`.
+        let mut components: Vec<Component> = Vec::new();
+        for cid in &live {
+            let vi = cdb.clause[*cid as usize]
+                .lits
+                .iter()
+                .map(|l| l.vi())
+                .find(|vi| unassigned(*vi))
+                .unwrap();
+            let r = find(&mut self.index, vi);
+            if self.position[r] == NO_COMPONENT {
+                self.position[r] = components.len();
+                components.push((Vec::new(), Vec::new()));
+            }
+            components[self.position[r]].1.push(*cid);
+        }
+        let mut num_free = 0;
+        for vi in vs {
+            if !unassigned(*vi) {
+                continue;
+            }
+            let r = find(&mut self.index, *vi);
+            match self.position[r] {
+                NO_COMPONENT if self.projected[*vi] => num_free += 1,
+                NO_COMPONENT => (),
+                i => components[i].0.push(*vi),
+            }
+        }
+        for vi in vs {
+            self.position[*vi] = NO_COMPONENT;
+        }
+        (components, num_free)
+    }
+}
+
+/// returns the root of `vi` in union-find with path halving.
+fn find(parent: &mut [usize], mut vi: VarId) -> VarId {
+    while parent[vi] != vi {
+        parent[vi] = parent[parent[vi]];
+        vi = parent[vi];
+    }
+    vi
+}
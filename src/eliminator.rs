@@ -1,4 +1,5 @@
 use crate::clause::{Clause, ClauseDB};
+use crate::proof::antecedents;
 use crate::propagator::AssignStack;
 use crate::state::State;
 use crate::traits::*;
@@ -41,6 +42,9 @@ impl EliminatorIF for Eliminator {
         e.var_queue = VarOccHeap::new(nv, 0);
         e
     }
+    fn new_var(&mut self) {
+        self.var_queue.new_var();
+    }
     fn activate(&mut self) {
         debug_assert!(self.mode != EliminatorMode::Running);
         self.mode = EliminatorMode::Waiting;
@@ -108,7 +112,7 @@ impl EliminatorIF for Eliminator {
         self.clause_queue.clear();
     }
     fn enqueue_var(&mut self, vars: &mut [Var], vi: VarId, upward: bool) {
-        if self.mode != EliminatorMode::Running {
+        if self.mode != EliminatorMode::Running || vars[vi].is(Flag::FROZEN) {
             return;
         }
         self.var_queue.insert(vars, vi, upward);
@@ -135,9 +139,10 @@ impl EliminatorIF for Eliminator {
             return Ok(());
         }
         let mut cnt = 0;
-        while self.bwdsub_assigns < asgs.len()
+        while (self.bwdsub_assigns < asgs.len()
             || !self.var_queue.is_empty()
-            || !self.clause_queue.is_empty()
+            || !self.clause_queue.is_empty())
+            && !state.is_interrupted()
         {
             if !self.clause_queue.is_empty() || self.bwdsub_assigns < asgs.len() {
                 self.backward_subsumption_check(asgs, cdb, state, vars)?;
@@ -145,7 +150,18 @@ impl EliminatorIF for Eliminator {
             while let Some(vi) = self.var_queue.select_var(vars) {
                 let v = &mut vars[vi];
                 v.turn_off(Flag::ENQUEUED);
+                // elimination is optional; stop here and leave the rest to `search`.
+                if state.is_interrupted() {
+                    self.clear_var_queue(vars);
+                    break;
+                }
                 cnt += 1;
+                // let observers stop a long elimination through the interrupt flag.
+                if cnt & 0xFF == 0 {
+                    for o in &mut state.observers {
+                        o.on_simplify();
+                    }
+                }
                 if cnt < state.elim_eliminate_loop_limit
                     && !v.is(Flag::ELIMINATED)
                     && v.assign == BOTTOM
@@ -159,6 +175,7 @@ impl EliminatorIF for Eliminator {
             if asgs.propagate(cdb, state, vars) != NULL_CLAUSE {
                 return Err(SolverError::Inconsistent);
             }
+            cdb.certificate_units(asgs, vars);
             cdb.eliminate_satisfied_clauses(self, vars, true);
             cdb.garbage_collect();
         }
@@ -206,6 +223,54 @@ impl EliminatorIF for Eliminator {
             i -= width;
         }
     }
+    fn take_eliminated_clauses(&mut self, vi: VarId) -> Vec<Vec<Lit>> {
+        // the records of clauses, each of which is followed by its width, are
+        // found from the top, and the var is the first literal in its records.
+        let mut records = Vec::new();
+        let mut i = self.elim_clauses.len();
+        while 0 < i {
+            let width = self.elim_clauses[i - 1] as usize;
+            i -= width + 1;
+            records.push((i, width));
+        }
+        let mut clauses = Vec::new();
+        let mut rest = Vec::with_capacity(self.elim_clauses.len());
+        for (i, width) in records.into_iter().rev() {
+            let c = &self.elim_clauses[i..i + width];
+            if c[0].vi() != vi {
+                rest.extend_from_slice(&self.elim_clauses[i..=i + width]);
+            } else if 1 < width {
+                // skip the unit clause which holds the default value of the var
+                clauses.push(c.to_vec());
+            }
+        }
+        self.elim_clauses = rest;
+        clauses
+    }
+    fn reconstruction_stack(&self) -> Vec<Vec<i32>> {
+        let mut stack = Vec::new();
+        let mut i = self.elim_clauses.len();
+        while 0 < i {
+            let width = self.elim_clauses[i - 1] as usize;
+            i -= width + 1;
+            stack.push(
+                self.elim_clauses[i..i + width]
+                    .iter()
+                    .map(|l| l.to_i32())
+                    .collect::<Vec<i32>>(),
+            );
+        }
+        stack.reverse();
+        stack
+    }
+    fn extend_reconstruction_stack(&mut self, stack: &[Vec<i32>]) {
+        for c in stack {
+            debug_assert!(!c.is_empty());
+            self.elim_clauses
+                .extend(c.iter().map(|i| Lit::from_int(*i)));
+            self.elim_clauses.push(c.len() as Lit);
+        }
+    }
     fn add_cid_occur(&mut self, vars: &mut [Var], cid: ClauseId, c: &mut Clause, enqueue: bool) {
         if self.mode != EliminatorMode::Running || c.is(Flag::OCCUR_LINKED) {
             return;
@@ -370,7 +435,7 @@ fn try_subsume(
         }
         Some(l) => {
             // println!("BackSubC subsumes {} from {} and {}", l.int(), cid.format(), did.format());
-            strengthen_clause(cdb, elim, vars, asgs, did, l.negate())?;
+            strengthen_clause(cdb, elim, vars, asgs, did, l.negate(), cid)?;
             elim.enqueue_var(vars, l.vi(), true);
         }
         None => {}
@@ -516,7 +581,7 @@ fn merge(cdb: &mut ClauseDB, cip: ClauseId, ciq: ClauseId, v: VarId, vec: &mut V
     vec.len()
 }
 
-/// removes `l` from clause `cid`
+/// removes `l` from clause `cid`, which is subsumed by clause `by` except `l`
 /// - calls `enqueue_clause`
 /// - calls `enqueue_var`
 fn strengthen_clause(
@@ -526,18 +591,50 @@ fn strengthen_clause(
     asgs: &mut AssignStack,
     cid: ClauseId,
     l: Lit,
+    by: ClauseId,
 ) -> MaybeInconsistent {
     debug_assert!(!cdb.clause[cid as usize].is(Flag::DEAD));
     debug_assert!(1 < cdb.clause[cid as usize].lits.len());
     cdb.touched[l as usize] = true;
     cdb.touched[l.negate() as usize] = true;
     debug_assert_ne!(cid, NULL_CLAUSE);
+    let hints = if cdb.use_hints() {
+        let c = &cdb.clause[cid as usize];
+        let lemma = c
+            .lits
+            .iter()
+            .filter(|q| **q != l)
+            .copied()
+            .collect::<Vec<Lit>>();
+        let tail = if by.is_lifted_lit() {
+            None
+        } else {
+            Some((
+                l.vi(),
+                &cdb.clause[by as usize].lits[..],
+                cdb.proof_id[by as usize],
+            ))
+        };
+        antecedents(
+            cdb,
+            asgs,
+            vars,
+            &lemma,
+            &[],
+            tail,
+            (&c.lits, cdb.proof_id[cid as usize]),
+        )
+        .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
     if strengthen(cdb, cid, l) {
         // Vaporize the binary clause
         debug_assert!(2 == cdb.clause[cid as usize].lits.len());
         let c0 = cdb.clause[cid as usize].lits[0];
         debug_assert_ne!(c0, l);
         // println!("{} {:?} is removed and its first literal {} is enqueued.", cid.format(), vec2int(&cdb.clause[cid].lits), c0.int());
+        cdb.proof_unit[c0.vi()] = cdb.certificate_add(&[c0], &hints);
         cdb.detach(cid);
         elim.remove_cid_occur(vars, cid, &mut cdb.clause[cid as usize]);
         asgs.enqueue(&mut vars[c0.vi()], c0.lbool(), NULL_CLAUSE, 0)
@@ -546,9 +643,13 @@ fn strengthen_clause(
         debug_assert!(1 < cdb.clause[cid as usize].lits.len());
         elim.enqueue_clause(cid, &mut cdb.clause[cid as usize]);
         elim.remove_lit_occur(vars, l, cid);
-        unsafe {
-            let vec = &cdb.clause[cid as usize].lits[..] as *const [Lit];
-            cdb.certificate_add(&*vec);
+        if cdb.certified.is_some() {
+            let new = cdb.clause[cid as usize].lits.clone();
+            let id = cdb.certificate_add(&new, &hints);
+            let mut old = new;
+            old.push(l);
+            cdb.certificate_delete(cdb.proof_id[cid as usize], &old);
+            cdb.proof_id[cid as usize] = id;
         }
         Ok(())
     }
@@ -646,7 +747,7 @@ fn eliminate_var(
     vi: VarId,
 ) -> MaybeInconsistent {
     let v = &mut vars[vi];
-    if v.assign != BOTTOM {
+    if v.assign != BOTTOM || v.is(Flag::FROZEN) {
         return Ok(());
     }
     debug_assert!(!v.is(Flag::ELIMINATED));
@@ -669,6 +770,11 @@ fn eliminate_var(
         for p in &*pos {
             let rank_p = cdb.clause[*p as usize].rank;
             for n in &*neg {
+                let hints = if cdb.use_hints() {
+                    vec![cdb.proof_id[*p as usize], cdb.proof_id[*n as usize]]
+                } else {
+                    Vec::new()
+                };
                 // println!("eliminator replaces {} with a cross product {:?}", p.fmt(), vec2int(&vec));
                 match merge(cdb, *p, *n, vi, &mut *vec) {
                     0 => (),
@@ -682,7 +788,7 @@ fn eliminate_var(
                         //     vec2int(&clause!(*cp, *n).lits)
                         // );
                         let lit = (*vec)[0];
-                        cdb.certificate_add(&*vec);
+                        cdb.proof_unit[lit.vi()] = cdb.certificate_add(&*vec, &hints);
                         asgs.enqueue(&mut vars[lit.vi()], lit.lbool(), NULL_CLAUSE, 0)?;
                     }
                     _ => {
@@ -693,6 +799,7 @@ fn eliminate_var(
                         } else {
                             0
                         };
+                        cdb.proof_hints = hints;
                         let cid = cdb.attach(state, vars, rank);
                         elim.add_cid_occur(vars, cid, &mut cdb.clause[cid as usize], true);
                     }
@@ -759,19 +866,15 @@ fn make_eliminated_clauses(
     neg: &[ClauseId],
 ) {
     let tmp = &mut elim.elim_clauses;
-    if neg.len() < pos.len() {
-        for cid in neg {
-            debug_assert!(!cdb.clause[*cid as usize].is(Flag::DEAD));
-            make_eliminated_clause(cdb, tmp, v, *cid);
-        }
-        make_eliminating_unit_clause(tmp, Lit::from_var(v, TRUE));
-    } else {
-        for cid in pos {
-            debug_assert!(!cdb.clause[*cid as usize].is(Flag::DEAD));
-            make_eliminated_clause(cdb, tmp, v, *cid);
-        }
-        make_eliminating_unit_clause(tmp, Lit::from_var(v, FALSE));
+    // Both sides are saved, not only the smaller one, in order to bring the var back
+    // when a clause added later refers it. It doesn't affect model extension
+    // because either side is satisfied by the resolvents.
+    for cid in pos.iter().chain(neg.iter()) {
+        debug_assert!(!cdb.clause[*cid as usize].is(Flag::DEAD));
+        make_eliminated_clause(cdb, tmp, v, *cid);
     }
+    let default = if neg.len() < pos.len() { TRUE } else { FALSE };
+    make_eliminating_unit_clause(tmp, Lit::from_var(v, default));
 }
 
 impl Var {
@@ -793,6 +896,7 @@ pub struct VarOccHeap {
 
 trait VarOrderIF {
     fn new(n: usize, init: usize) -> VarOccHeap;
+    fn new_var(&mut self);
     fn insert(&mut self, vec: &[Var], vi: VarId, upword: bool);
     fn clear(&mut self, vars: &mut [Var]);
     fn len(&self) -> usize;
@@ -814,6 +918,11 @@ impl VarOrderIF for VarOccHeap {
         idxs[0] = init;
         VarOccHeap { heap, idxs }
     }
+    fn new_var(&mut self) {
+        let vi = self.heap.len();
+        self.heap.push(vi);
+        self.idxs.push(vi);
+    }
     fn insert(&mut self, vars: &[Var], vi: VarId, upward: bool) {
         debug_assert!(vi < self.heap.len());
         if self.contains(vi) {
@@ -860,7 +969,7 @@ impl VarOrderIF for VarOccHeap {
     fn rebuild(&mut self, vars: &[Var]) {
         self.reset();
         for v in &vars[1..] {
-            if v.assign == BOTTOM && !v.is(Flag::ELIMINATED) {
+            if v.assign == BOTTOM && !v.is(Flag::ELIMINATED) && !v.is(Flag::FROZEN) {
                 self.insert(vars, v.index, true);
             }
         }
@@ -0,0 +1,257 @@
+use crate::config::Config;
+use crate::solver::{Certificate, Solver};
+use crate::state::{InterruptHandle, ProgressRecord};
+use crate::traits::{LitIF, ObserverIF, SatSolverIF};
+use crate::types::{CNFDescription, Lit};
+use std::cell::RefCell;
+use std::os::raw::{c_char, c_int, c_void};
+use std::rc::Rc;
+
+/// the return value of `ipasir_signature`
+const SIGNATURE: &str = concat!("splr-", env!("CARGO_PKG_VERSION"), "\0");
+
+/// the callback of `ipasir_set_terminate`
+pub type TerminateCallback = unsafe extern "C" fn(data: *mut c_void) -> c_int;
+
+/// the callback of `ipasir_set_learn`
+pub type LearnCallback = unsafe extern "C" fn(data: *mut c_void, clause: *mut i32);
+
+/// A solver behind the IPASIR interface, which is a `void *` in C.
+pub struct IpasirSolver {
+    solver: Solver,
+    /// the clause being added by `ipasir_add`
+    clause: Vec<Lit>,
+    /// the assumptions for the next `ipasir_solve`
+    assumptions: Vec<Lit>,
+    /// the model found by the last `ipasir_solve`
+    model: Vec<i32>,
+    /// the failed assumptions found by the last `ipasir_solve`
+    failed: Vec<i32>,
+    callbacks: Rc<RefCell<Callbacks>>,
+}
+
+/// The callbacks set by the user, shared with the observer in the solver.
+struct Callbacks {
+    terminate: Option<(*mut c_void, TerminateCallback)>,
+    learn: Option<(*mut c_void, usize, LearnCallback)>,
+    interrupt: InterruptHandle,
+    /// a zero-terminated clause passed to `learn`
+    buffer: Vec<i32>,
+}
+
+/// calls the callbacks during search and simplification.
+struct CallbackCaller(Rc<RefCell<Callbacks>>);
+
+impl ObserverIF for CallbackCaller {
+    fn on_learnt(&mut self, lits: &[Lit], _lbd: usize) {
+        let mut cb = self.0.borrow_mut();
+        if let Some((data, max_length, learn)) = cb.learn {
+            if lits.len() <= max_length {
+                cb.buffer.clear();
+                cb.buffer.extend(lits.iter().map(|l| l.to_i32()));
+                cb.buffer.push(0);
+                unsafe { learn(data, cb.buffer.as_mut_ptr()) };
+            }
+        }
+        cb.poll();
+    }
+    fn on_restart(&mut self) {
+        self.0.borrow().poll();
+    }
+    fn on_simplify(&mut self) {
+        self.0.borrow().poll();
+    }
+    fn on_progress(&mut self, _record: &ProgressRecord) {
+        self.0.borrow().poll();
+    }
+}
+
+impl Callbacks {
+    /// asks the terminate callback whether to stop, then interrupts the solver.
+    fn poll(&self) {
+        if let Some((data, terminate)) = self.terminate {
+            if unsafe { terminate(data) } != 0 {
+                self.interrupt.interrupt();
+            }
+        }
+    }
+}
+
+impl IpasirSolver {
+    fn new() -> IpasirSolver {
+        let mut solver = Solver::new(&Config::default(), &CNFDescription::default());
+        let callbacks = Rc::new(RefCell::new(Callbacks {
+            terminate: None,
+            learn: None,
+            interrupt: solver.interrupt_handle(),
+            buffer: Vec::new(),
+        }));
+        solver.add_observer(Box::new(CallbackCaller(Rc::clone(&callbacks))));
+        IpasirSolver {
+            solver,
+            clause: Vec::new(),
+            assumptions: Vec::new(),
+            model: Vec::new(),
+            failed: Vec::new(),
+            callbacks,
+        }
+    }
+    /// adds vars up to the var of `lit`.
+    fn reserve_var(&mut self, lit: i32) {
+        self.solver.reserve_var(lit.unsigned_abs() as usize);
+    }
+    fn add(&mut self, lit: i32) {
+        if lit == 0 {
+            let mut clause = std::mem::take(&mut self.clause);
+            self.solver.add_unchecked_clause(&mut clause);
+        } else {
+            self.reserve_var(lit);
+            self.clause.push(Lit::from_int(lit));
+        }
+    }
+    fn assume(&mut self, lit: i32) {
+        // 0 isn't a literal; the standard leaves it undefined.
+        if lit == 0 {
+            return;
+        }
+        self.reserve_var(lit);
+        self.assumptions.push(Lit::from_int(lit));
+    }
+    fn solve(&mut self) -> c_int {
+        let assumptions = std::mem::take(&mut self.assumptions);
+        self.model.clear();
+        self.failed.clear();
+        match self.solver.solve_with_assumptions(&assumptions) {
+            Ok(Certificate::SAT(v)) => {
+                self.model = v;
+                10
+            }
+            Ok(Certificate::UNSAT) => 20,
+            Ok(Certificate::UNSATUnderAssumptions(v)) => {
+                self.failed = v;
+                20
+            }
+            _ => 0,
+        }
+    }
+    fn val(&self, lit: i32) -> i32 {
+        match (lit.unsigned_abs() as usize)
+            .checked_sub(1)
+            .and_then(|i| self.model.get(i))
+        {
+            Some(v) if *v == lit => lit,
+            Some(v) if *v == -lit => -lit,
+            _ => 0,
+        }
+    }
+}
+
+/// returns the name and the version of the solver.
+#[no_mangle]
+pub extern "C" fn ipasir_signature() -> *const c_char {
+    SIGNATURE.as_ptr() as *const c_char
+}
+
+/// returns a new solver, which should be released by `ipasir_release`.
+#[no_mangle]
+pub extern "C" fn ipasir_init() -> *mut c_void {
+    Box::into_raw(Box::new(IpasirSolver::new())) as *mut c_void
+}
+
+/// releases a solver.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`, and isn't used after this.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_release(solver: *mut c_void) {
+    if !solver.is_null() {
+        drop(Box::from_raw(solver as *mut IpasirSolver));
+    }
+}
+
+/// adds a literal to the clause being added, or closes it with `0`.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_add(solver: *mut c_void, lit_or_zero: i32) {
+    (*(solver as *mut IpasirSolver)).add(lit_or_zero);
+}
+
+/// adds an assumption for the next `ipasir_solve`.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_assume(solver: *mut c_void, lit: i32) {
+    (*(solver as *mut IpasirSolver)).assume(lit);
+}
+
+/// solves the clauses under the assumptions, which are cleared after this.
+/// Returns 10 if satisfiable, 20 if unsatisfiable, or 0 if interrupted.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_solve(solver: *mut c_void) -> c_int {
+    (*(solver as *mut IpasirSolver)).solve()
+}
+
+/// returns `lit` if it's true, `-lit` if false, or 0 if unknown in the last model.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_val(solver: *mut c_void, lit: i32) -> i32 {
+    (*(solver as *mut IpasirSolver)).val(lit)
+}
+
+/// returns 1 if the assumption `lit` was used to prove the last unsatisfiability, or 0.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_failed(solver: *mut c_void, lit: i32) -> c_int {
+    (*(solver as *mut IpasirSolver)).failed.contains(&lit) as c_int
+}
+
+/// sets a callback to stop `ipasir_solve` by returning a non-zero value, or removes it by `NULL`.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`, and `terminate` takes `data` safely.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_terminate(
+    solver: *mut c_void,
+    data: *mut c_void,
+    terminate: Option<TerminateCallback>,
+) {
+    let s = &mut *(solver as *mut IpasirSolver);
+    s.callbacks.borrow_mut().terminate = terminate.map(|f| (data, f));
+}
+
+/// sets a callback to receive learnt clauses up to `max_length` literals, each of which is
+/// terminated by `0`, or removes it by `NULL`.
+///
+/// # Safety
+///
+/// `solver` should be returned by `ipasir_init`, and `learn` takes `data` safely.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_learn(
+    solver: *mut c_void,
+    data: *mut c_void,
+    max_length: c_int,
+    learn: Option<LearnCallback>,
+) {
+    let s = &mut *(solver as *mut IpasirSolver);
+    s.callbacks.borrow_mut().learn = match learn {
+        Some(f) if 0 <= max_length => Some((data, max_length as usize, f)),
+        _ => None,
+    };
+}
@@ -154,8 +154,14 @@ While Splr comes with **ABSOLUTELY NO WARRANTY**, Splr version 0.1.0 (splr-0.1.0
 pub mod clause;
 /// Parameters used for Solver initialization
 pub mod config;
+/// Exact model counting with component caching
+pub mod counter;
 /// Pre/In-processor for clause subsumption and variable elimination
 pub mod eliminator;
+/// IPASIR, the C interface for incremental SAT solving
+pub mod ipasir;
+/// DRAT certification output
+pub mod proof;
 /// Assignment management
 pub mod propagator;
 /// Solver restart implementation
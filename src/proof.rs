@@ -0,0 +1,305 @@
+use crate::clause::{CertifiedRecord, ClauseDB};
+use crate::propagator::AssignStack;
+use crate::traits::{LitIF, ProofSinkIF, VarDBIF};
+use crate::types::{Lit, ProofId, VarId, FALSE, NULL_CLAUSE};
+use crate::var::Var;
+use std::collections::{hash_map::Entry, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+impl fmt::Debug for dyn ProofSinkIF {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProofSink")
+    }
+}
+
+/// A proof sink keeping all DRAT records in memory.
+#[derive(Debug, Default)]
+pub struct ProofMemory {
+    pub records: Vec<(CertifiedRecord, Vec<i32>)>,
+}
+
+impl ProofSinkIF for ProofMemory {
+    fn record(&mut self, kind: CertifiedRecord, _id: ProofId, lits: &[Lit], _hints: &[ProofId]) {
+        if kind == CertifiedRecord::ADD || kind == CertifiedRecord::DELETE {
+            self.records
+                .push((kind, lits.iter().map(|l| l.to_i32()).collect::<Vec<i32>>()));
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+    fn records(&self) -> Option<&[(CertifiedRecord, Vec<i32>)]> {
+        Some(&self.records)
+    }
+}
+
+/// Output formats of `ProofWriter`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProofFormat {
+    /// DRAT in text
+    Text,
+    /// DRAT in the binary encoding
+    Binary,
+    /// FRAT in text, with clause ids and antecedents
+    FRAT,
+}
+
+/// A proof sink writing records through during search.
+/// After an IO error, it stops writing and `flush` returns the error.
+#[derive(Debug)]
+pub struct ProofWriter<W: Write> {
+    out: W,
+    format: ProofFormat,
+    error: Option<io::Error>,
+    /// clauses to be finalized in FRAT
+    alive: HashMap<ProofId, Vec<i32>>,
+    /// whether the empty clause has been written
+    refuted: bool,
+}
+
+impl ProofWriter<BufWriter<File>> {
+    /// creates a proof file.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        format: ProofFormat,
+    ) -> io::Result<ProofWriter<BufWriter<File>>> {
+        Ok(ProofWriter::new(
+            BufWriter::new(File::create(path)?),
+            format,
+        ))
+    }
+}
+
+impl<W: Write> ProofWriter<W> {
+    pub fn new(out: W, format: ProofFormat) -> ProofWriter<W> {
+        ProofWriter {
+            out,
+            format,
+            error: None,
+            alive: HashMap::new(),
+            refuted: false,
+        }
+    }
+    /// returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+    fn write(
+        &mut self,
+        kind: CertifiedRecord,
+        id: ProofId,
+        lits: &[Lit],
+        hints: &[ProofId],
+    ) -> io::Result<()> {
+        match self.format {
+            ProofFormat::Text => self.write_text(kind, lits),
+            ProofFormat::Binary => self.write_binary(kind, lits),
+            ProofFormat::FRAT => self.write_frat(kind, id, lits, hints),
+        }
+    }
+    fn write_text(&mut self, kind: CertifiedRecord, lits: &[Lit]) -> io::Result<()> {
+        match kind {
+            CertifiedRecord::ADD => (),
+            CertifiedRecord::DELETE => self.out.write_all(b"d ")?,
+            _ => return Ok(()),
+        }
+        for l in lits {
+            write!(self.out, "{} ", l.to_i32())?;
+        }
+        self.out.write_all(b"0\n")
+    }
+    /// writes a record as `a` or `d` followed by literals in a variable-length encoding
+    /// of `2 * var + sign`, and a terminating zero byte.
+    fn write_binary(&mut self, kind: CertifiedRecord, lits: &[Lit]) -> io::Result<()> {
+        let head = match kind {
+            CertifiedRecord::ADD => b'a',
+            CertifiedRecord::DELETE => b'd',
+            _ => return Ok(()),
+        };
+        self.out.write_all(&[head])?;
+        for l in lits {
+            let i = l.to_i32();
+            let mut u = 2 * i.unsigned_abs() + (i < 0) as u32;
+            while 0x7F < u {
+                self.out.write_all(&[(u & 0x7F) as u8 | 0x80])?;
+                u >>= 7;
+            }
+            self.out.write_all(&[u as u8])?;
+        }
+        self.out.write_all(&[0])
+    }
+    /// writes a record as `o`, `a`, or `d` with its id, and antecedents after `l` if any.
+    fn write_frat(
+        &mut self,
+        kind: CertifiedRecord,
+        id: ProofId,
+        lits: &[Lit],
+        hints: &[ProofId],
+    ) -> io::Result<()> {
+        let head = match kind {
+            CertifiedRecord::ORIGINAL => "o",
+            CertifiedRecord::ADD => "a",
+            CertifiedRecord::DELETE => "d",
+            CertifiedRecord::SENTINEL => return Ok(()),
+        };
+        let vec = lits.iter().map(|l| l.to_i32()).collect::<Vec<i32>>();
+        write!(self.out, "{} {} ", head, id)?;
+        for l in &vec {
+            write!(self.out, "{} ", l)?;
+        }
+        self.out.write_all(b"0")?;
+        if kind == CertifiedRecord::ADD && !hints.is_empty() {
+            self.out.write_all(b" l ")?;
+            for h in hints {
+                write!(self.out, "{} ", h)?;
+            }
+            self.out.write_all(b"0")?;
+        }
+        self.out.write_all(b"\n")?;
+        if kind == CertifiedRecord::DELETE {
+            self.alive.remove(&id);
+        } else {
+            self.alive.insert(id, vec);
+        }
+        Ok(())
+    }
+    fn write_final(&mut self) -> io::Result<()> {
+        let mut ids = self.alive.keys().copied().collect::<Vec<ProofId>>();
+        ids.sort_unstable();
+        for id in ids {
+            write!(self.out, "f {} ", id)?;
+            for l in &self.alive[&id] {
+                write!(self.out, "{} ", l)?;
+            }
+            self.out.write_all(b"0\n")?;
+        }
+        self.alive.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> ProofSinkIF for ProofWriter<W> {
+    fn record(&mut self, kind: CertifiedRecord, id: ProofId, lits: &[Lit], hints: &[ProofId]) {
+        // a proof ends at the first empty clause.
+        if self.error.is_some() || (self.refuted && kind == CertifiedRecord::ADD && lits.is_empty())
+        {
+            return;
+        }
+        if kind == CertifiedRecord::ADD && lits.is_empty() {
+            self.refuted = true;
+        }
+        if let Err(e) = self.write(kind, id, lits, hints) {
+            self.error = Some(e);
+        }
+    }
+    fn finalize(&mut self) {
+        if self.format != ProofFormat::FRAT || self.error.is_some() {
+            return;
+        }
+        if let Err(e) = self.write_final() {
+            self.error = Some(e);
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.flush()
+    }
+    fn use_hints(&self) -> bool {
+        self.format == ProofFormat::FRAT
+    }
+}
+
+const GIVEN: u8 = 1;
+const NEEDED: u8 = 2;
+
+/// marks the var of a false literal `l` as needed to be justified; returns `false` if `l` isn't false.
+fn need(vars: &[Var], mark: &mut HashMap<VarId, u8>, count: &mut usize, l: Lit) -> bool {
+    if let Entry::Vacant(e) = mark.entry(l.vi()) {
+        if vars.assigned(l) != FALSE {
+            return false;
+        }
+        e.insert(NEEDED);
+        *count += 1;
+    }
+    true
+}
+
+/// returns the ids of clauses deriving `lemma` by unit propagation in the LRAT order, or `None`
+/// if some of them are unknown. The propagation starts from the negation of `lemma`, goes on
+/// `head`, each of which becomes a unit by the negation of `lemma` only, the trail, and `tail`
+/// which becomes a unit on a var, then it reaches a conflict on `start`.
+/// All the other literals in `start` and `tail` must be false under the current assignment.
+pub fn antecedents(
+    cdb: &ClauseDB,
+    asgs: &AssignStack,
+    vars: &[Var],
+    lemma: &[Lit],
+    head: &[(VarId, ProofId)],
+    tail: Option<(VarId, &[Lit], ProofId)>,
+    start: (&[Lit], ProofId),
+) -> Option<Vec<ProofId>> {
+    let mut mark: HashMap<VarId, u8> = HashMap::new();
+    let mut count = 0;
+    let mut hints = Vec::new();
+    for l in lemma {
+        mark.insert(l.vi(), GIVEN);
+    }
+    for (vi, id) in head {
+        mark.insert(*vi, GIVEN);
+        hints.push(*id);
+    }
+    if let Some((vi, lits, _)) = tail {
+        mark.insert(vi, GIVEN);
+        for l in lits {
+            if !need(vars, &mut mark, &mut count, *l) {
+                return None;
+            }
+        }
+    }
+    for l in start.0 {
+        if !need(vars, &mut mark, &mut count, *l) {
+            return None;
+        }
+    }
+    let mut chain = Vec::new();
+    for l in asgs.trail.iter().rev() {
+        if count == 0 {
+            break;
+        }
+        let vi = l.vi();
+        if mark.get(&vi) != Some(&NEEDED) {
+            continue;
+        }
+        mark.insert(vi, GIVEN);
+        count -= 1;
+        if vars[vi].level == 0 && cdb.proof_unit[vi] != 0 {
+            chain.push(cdb.proof_unit[vi]);
+            continue;
+        }
+        let cid = vars[vi].reason;
+        if cid == NULL_CLAUSE || cdb.clause[cid as usize].lits.is_empty() {
+            return None;
+        }
+        chain.push(cdb.proof_id[cid as usize]);
+        for q in &cdb.clause[cid as usize].lits {
+            if q.vi() != vi && !need(vars, &mut mark, &mut count, *q) {
+                return None;
+            }
+        }
+    }
+    if 0 < count {
+        return None;
+    }
+    chain.reverse();
+    hints.append(&mut chain);
+    if let Some((_, _, id)) = tail {
+        hints.push(id);
+    }
+    hints.push(start.1);
+    Some(hints)
+}
@@ -1,11 +1,12 @@
 use crate::clause::{ClauseDB, Watch};
 use crate::state::{Stat, State};
-use crate::traits::{FlagIF, LitIF, PropagatorIF, WatchDBIF};
+use crate::traits::{FlagIF, LitIF, PropagatorIF, VarDBIF, WatchDBIF};
 use crate::types::*;
 use crate::var::Var;
 use std::fmt;
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 
 /// A record of assignment. It's called 'trail' in Glucose.
 #[derive(Debug)]
@@ -27,6 +28,11 @@ impl PropagatorIF for AssignStack {
             var_order: VarIdHeap::new(n, n),
         }
     }
+    fn new_var(&mut self, vars: &[Var]) {
+        self.assign.push(BOTTOM);
+        self.var_order.new_var();
+        self.var_order.insert(vars, vars.len() - 1);
+    }
     fn len(&self) -> usize {
         self.trail.len()
     }
@@ -185,6 +191,21 @@ impl PropagatorIF for AssignStack {
         self.trail_lim.truncate(lv);
         self.q_head = lim;
     }
+    fn unassign(&mut self, vars: &mut [Var], vi: VarId) {
+        debug_assert!(self.trail_lim.is_empty());
+        if let Some(i) = self.trail.iter().position(|l| l.vi() == vi) {
+            self.trail.remove(i);
+            if i < self.q_head {
+                self.q_head -= 1;
+            }
+            let v = &mut vars[vi];
+            v.phase = self.assign[vi];
+            self.assign[vi] = BOTTOM;
+            v.assign = BOTTOM;
+            v.reason = NULL_CLAUSE;
+            self.var_order.insert(vars, vi);
+        }
+    }
     fn uncheck_enqueue(&mut self, vars: &mut [Var], l: Lit, cid: ClauseId) {
         debug_assert!(l != 0, "Null literal is about to be equeued");
         debug_assert!(
@@ -204,6 +225,19 @@ impl PropagatorIF for AssignStack {
         debug_assert!(!self.trail.contains(&l.negate()));
         self.trail.push(l);
     }
+    fn assume(&mut self, vars: &mut [Var], l: Lit) -> MaybeInconsistent {
+        match self.assigned(l) {
+            FALSE => Err(SolverError::Inconsistent),
+            TRUE => {
+                self.level_up();
+                Ok(())
+            }
+            _ => {
+                self.uncheck_assume(vars, l);
+                Ok(())
+            }
+        }
+    }
     fn uncheck_assume(&mut self, vars: &mut [Var], l: Lit) {
         debug_assert!(!self.trail.contains(&l));
         debug_assert!(!self.trail.contains(&l.negate()));
@@ -225,36 +259,58 @@ impl PropagatorIF for AssignStack {
     fn update_order(&mut self, vec: &[Var], v: VarId) {
         self.var_order.update(vec, v)
     }
+    fn insert_order(&mut self, vec: &[Var], v: VarId) {
+        self.var_order.insert(vec, v)
+    }
     #[allow(dead_code)]
-    fn dump_cnf(&mut self, cdb: &ClauseDB, state: &State, vars: &[Var], fname: &str) {
-        for v in vars {
-            if v.is(Flag::ELIMINATED) {
-                if self.assign[v.index] != BOTTOM {
-                    panic!("conflicting var {} {}", v.index, self.assign[v.index]);
-                } else {
-                    println!("eliminate var {}", v.index);
-                }
-            }
+    fn dump_cnf(
+        &self,
+        cdb: &ClauseDB,
+        state: &State,
+        vars: &[Var],
+        fname: &Path,
+    ) -> io::Result<()> {
+        let mut buf = BufWriter::new(File::create(fname)?);
+        let nv = vars.len() - 1;
+        if !state.ok {
+            buf.write_all(format!("p cnf {} 1\n0\n", nv).as_bytes())?;
+            return buf.flush();
         }
-        if let Ok(out) = File::create(&fname) {
-            let mut buf = BufWriter::new(out);
-            let nv = self.len();
-            let nc: usize = cdb.clause.len() - 1;
-            buf.write_all(format!("p cnf {} {}\n", state.num_vars, nc + nv).as_bytes())
-                .unwrap();
-            for c in &cdb.clause[1..] {
-                for l in &c.lits {
-                    buf.write_all(format!("{} ", l.to_i32()).as_bytes())
-                        .unwrap();
-                }
-                buf.write_all(b"0\n").unwrap();
+        let fixed = |l: Lit| vars[l.vi()].assign != BOTTOM && vars[l.vi()].level == 0;
+        let units = match self.trail_lim.first() {
+            Some(n) => &self.trail[..*n],
+            None => &self.trail[..],
+        };
+        let mut clauses: Vec<Vec<i32>> = Vec::new();
+        for c in &cdb.clause[1..] {
+            if c.is(Flag::DEAD)
+                || c.is(Flag::LEARNT)
+                || c.lits.is_empty()
+                || c.lits
+                    .iter()
+                    .any(|l| fixed(*l) && vars.assigned(*l) == TRUE)
+            {
+                continue;
             }
-            buf.write_all(b"c from trail\n").unwrap();
-            for x in &self.trail {
-                buf.write_all(format!("{} 0\n", x.to_i32()).as_bytes())
-                    .unwrap();
+            clauses.push(
+                c.lits
+                    .iter()
+                    .filter(|l| !fixed(**l))
+                    .map(|l| l.to_i32())
+                    .collect::<Vec<i32>>(),
+            );
+        }
+        buf.write_all(format!("p cnf {} {}\n", nv, clauses.len() + units.len()).as_bytes())?;
+        for c in &clauses {
+            for l in c {
+                buf.write_all(format!("{} ", l).as_bytes())?;
             }
+            buf.write_all(b"0\n")?;
         }
+        for l in units {
+            buf.write_all(format!("{} 0\n", l.to_i32()).as_bytes())?;
+        }
+        buf.flush()
     }
 }
 
@@ -285,6 +341,7 @@ pub struct VarIdHeap {
 
 trait VarOrderIF {
     fn new(n: usize, init: usize) -> VarIdHeap;
+    fn new_var(&mut self);
     fn update(&mut self, vec: &[Var], v: VarId);
     fn insert(&mut self, vec: &[Var], vi: VarId);
     fn clear(&mut self);
@@ -307,6 +364,12 @@ impl VarOrderIF for VarIdHeap {
         idxs[0] = init;
         VarIdHeap { heap, idxs }
     }
+    // the new var is placed out of the heap.
+    fn new_var(&mut self) {
+        let vi = self.heap.len();
+        self.heap.push(vi);
+        self.idxs.push(vi);
+    }
     fn update(&mut self, vec: &[Var], v: VarId) {
         debug_assert!(v != 0, "Invalid VarId");
         let start = self.idxs[v];
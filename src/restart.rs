@@ -62,6 +62,9 @@ impl RestartIF for State {
         {
             self.stats[Stat::Restart] += 1;
             self.after_restart = 0;
+            for o in &mut self.observers {
+                o.on_restart();
+            }
             if self.use_luby_restart {
                 *ncnfl = 0.0;
                 self.luby_current_restarts += 1;
@@ -1,11 +1,14 @@
 use crate::clause::{Clause, ClauseDB};
 use crate::config::Config;
 use crate::eliminator::Eliminator;
+use crate::proof::{antecedents, ProofFormat, ProofWriter};
 use crate::propagator::AssignStack;
-use crate::state::{Stat, State};
+use crate::state::{Budget, InterruptHandle, Stat, State};
 use crate::traits::*;
 use crate::types::*;
 use crate::var::Var;
+use std::cmp::Reverse;
+use std::convert::TryFrom;
 use std::fs;
 use std::io::{BufRead, BufReader};
 
@@ -14,6 +17,10 @@ use std::io::{BufRead, BufReader};
 pub enum Certificate {
     SAT(Vec<i32>),
     UNSAT,
+    /// the subset of assumptions which are responsible for unsatisfiability
+    UNSATUnderAssumptions(Vec<i32>),
+    /// stopped by running out of a budget
+    Unknown(Budget),
 }
 
 /// Abnormal termination flags.
@@ -22,18 +29,120 @@ pub enum SolverException {
     // StateUNSAT = 0,
     // StateSAT,
     Inconsistent,
+    /// stopped by `InterruptHandle::interrupt`
+    Interrupted,
+    /// a clause given as a vector of `i32` contains zero, or a var is out of range
+    InvalidLiteral,
     OutOfMemory,
     TimeOut,
     UndescribedError,
 }
 
 /// The return type of `Solver::solve`.
-/// This captures the following three cases:
+/// This captures the following five cases:
 /// * `Certificate::SAT` -- solved with a satisfiable assignment set,
-/// * `Certificate::UNSAT` -- proved that it's an unsatisfiable problem, and
+/// * `Certificate::UNSAT` -- proved that it's an unsatisfiable problem,
+/// * `Certificate::UNSATUnderAssumptions` -- proved that it's unsatisfiable under the assumptions,
+/// * `Certificate::Unknown` -- gave up due to a budget given by `Config`, and
 /// * `SolverException::*` -- caused by a bug
 pub type SolverResult = Result<Certificate, SolverException>;
 
+/// An iterator over models made by `SatSolverIF::models`.
+/// It ends when no more models exist or `solve` fails by a budget or an exception;
+/// `Models::result` tells them apart.
+#[derive(Debug)]
+pub struct Models<'a> {
+    solver: &'a mut Solver,
+    projection: Vec<VarId>,
+    /// a var assumed to be false during the iteration, which every blocking clause contains
+    activator: VarId,
+    result: Option<SolverResult>,
+}
+
+impl Models<'_> {
+    /// return the result which ended the iteration, or `None` if it continues.
+    /// It's `Ok(Certificate::UNSAT)` if all models have been enumerated;
+    /// `Ok(Certificate::Unknown(_))` or `Err(_)` means the iteration was cut short.
+    pub fn result(&self) -> Option<&SolverResult> {
+        self.result.as_ref()
+    }
+    /// return `true` if all models have been enumerated.
+    pub fn is_complete(&self) -> bool {
+        matches!(self.result, Some(Ok(Certificate::UNSAT)))
+    }
+}
+
+impl Iterator for Models<'_> {
+    type Item = Vec<i32>;
+    fn next(&mut self) -> Option<Vec<i32>> {
+        if self.result.is_some() {
+            return None;
+        }
+        let activator = Lit::from_int(self.activator as i32);
+        match self.solver.solve_with_assumptions(&[activator.negate()]) {
+            Ok(Certificate::SAT(model)) => {
+                let mut block = self
+                    .projection
+                    .iter()
+                    .map(|vi| Lit::from_int(-model[vi - 1]))
+                    .collect::<Vec<Lit>>();
+                // the last model is forced at level zero; a unit `activator` would fix it.
+                if block.iter().all(|l| self.solver.vars.assigned(*l) == FALSE) {
+                    self.result = Some(Ok(Certificate::UNSAT));
+                } else {
+                    block.push(activator);
+                    self.solver.add_unchecked_clause(&mut block);
+                }
+                Some(model)
+            }
+            Ok(Certificate::UNSATUnderAssumptions(_)) | Ok(Certificate::UNSAT) => {
+                self.result = Some(Ok(Certificate::UNSAT));
+                None
+            }
+            res => {
+                self.result = Some(res);
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Models<'_> {
+    fn drop(&mut self) {
+        // give back the models by removing the blocking clauses and the clauses learnt from
+        // them, all of which contain `activator`. Then it can be used in the next iteration.
+        let Solver {
+            ref mut asgs,
+            ref mut cdb,
+            ref mut elim,
+            ref mut vars,
+            ..
+        } = self.solver;
+        let activator = Lit::from_int(self.activator as i32);
+        for (cid, c) in cdb.clause.iter_mut().enumerate().skip(1) {
+            if !c.is(Flag::DEAD) && c.lits.contains(&activator) {
+                c.kill(&mut cdb.touched);
+                if elim.is_running() {
+                    elim.remove_cid_occur(vars, cid as ClauseId, c);
+                }
+            }
+        }
+        cdb.garbage_collect();
+        // A blocking clause propagates `activator` at level zero when the last model is forced.
+        if vars[self.activator].assign != BOTTOM {
+            let id = cdb.proof_unit[self.activator];
+            if id != 0 {
+                cdb.certificate_delete(id, &[activator]);
+                cdb.proof_unit[self.activator] = 0;
+            }
+            asgs.unassign(vars, self.activator);
+        }
+        for vi in &self.projection {
+            self.solver.melt(*vi);
+        }
+    }
+}
+
 /// SAT solver consisting of 5 sub modules.
 #[derive(Debug)]
 pub struct Solver {
@@ -73,6 +182,163 @@ impl SatSolverIF for Solver {
     /// }
     ///```
     fn solve(&mut self) -> SolverResult {
+        self.solve_with_assumptions(&[])
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::SatSolverIF;
+    /// use splr::config::Config;
+    /// use splr::solver::{Certificate, Solver, SolverException};
+    ///
+    /// let config = Config::from("tests/sample.cnf");
+    /// let mut s = Solver::build(&config).unwrap();
+    /// s.state.use_progress = false;
+    /// s.interrupt_handle().interrupt();
+    /// assert!(matches!(s.solve(), Err(SolverException::Interrupted)));
+    /// // the solver is still usable.
+    /// assert!(matches!(s.solve(), Ok(Certificate::SAT(_))));
+    ///```
+    fn interrupt_handle(&self) -> InterruptHandle {
+        self.state.interrupt.clone()
+    }
+    fn add_observer(&mut self, observer: Box<dyn ObserverIF>) {
+        self.state.observers.push(observer);
+    }
+    fn connect_propagator(&mut self, propagator: Box<dyn ExternalPropagatorIF>) {
+        debug_assert!(self.asgs.level() == 0);
+        self.state.propagator = Some(propagator);
+        self.state.propagator_trail = 0;
+        self.state.propagator_pending.clear();
+        self.state.propagator_facts.clear();
+    }
+    fn disconnect_propagator(&mut self) -> Option<Box<dyn ExternalPropagatorIF>> {
+        for vi in 1..self.vars.len() {
+            if self.vars[vi].is(Flag::OBSERVED) {
+                self.vars[vi].turn_off(Flag::OBSERVED);
+                self.melt(vi);
+            }
+        }
+        self.state.propagator.take()
+    }
+    fn add_observed_var(&mut self, vi: VarId) {
+        if self.vars[vi].is(Flag::OBSERVED) {
+            return;
+        }
+        self.freeze(vi);
+        let v = &mut self.vars[vi];
+        v.turn_on(Flag::OBSERVED);
+        if v.assign != BOTTOM {
+            if let Some(p) = self.state.propagator.as_mut() {
+                p.notify_assignment(&[Lit::from_var(vi, v.assign)], v.level);
+            }
+        }
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::{FlagIF, SatSolverIF};
+    /// use splr::config::Config;
+    /// use splr::solver::{Certificate, Solver};
+    /// use splr::types::Flag;
+    /// use std::convert::TryFrom;
+    ///
+    /// let mut s = Solver::try_from((Config::default(), vec![vec![1, 2], vec![-1, 3]])).unwrap();
+    /// s.state.use_progress = false;
+    /// s.freeze(1);
+    /// assert!(matches!(s.solve(), Ok(Certificate::SAT(_))));
+    /// assert!(!s.vars[1].is(Flag::ELIMINATED));
+    /// s.melt(1);
+    /// assert!(!s.vars[1].is(Flag::FROZEN));
+    ///```
+    fn freeze(&mut self, vi: VarId) {
+        self.restore_eliminated_var(vi);
+        let v = &mut self.vars[vi];
+        v.freezes += 1;
+        v.turn_on(Flag::FROZEN);
+    }
+    fn melt(&mut self, vi: VarId) {
+        let v = &mut self.vars[vi];
+        v.freezes = v.freezes.saturating_sub(1);
+        if v.freezes == 0 {
+            v.turn_off(Flag::FROZEN);
+        }
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::SatSolverIF;
+    /// use splr::config::Config;
+    /// use splr::solver::Solver;
+    /// use std::convert::TryFrom;
+    ///
+    /// let cnf = vec![vec![1, 2], vec![-1, 3]];
+    /// let mut s = Solver::try_from((Config::default(), cnf)).unwrap();
+    /// s.state.use_progress = false;
+    /// let mut models = s.models(&[]).unwrap();
+    /// assert_eq!(models.by_ref().count(), 4);
+    /// assert!(models.is_complete());
+    ///```
+    fn models(&mut self, projection: &[VarId]) -> Result<Models<'_>, SolverException> {
+        let num_vars = self.state.num_vars - self.state.activator.is_some() as usize;
+        if projection.iter().any(|vi| *vi == 0 || num_vars < *vi) {
+            return Err(SolverException::InvalidLiteral);
+        }
+        let projection = if projection.is_empty() {
+            (1..=num_vars).collect::<Vec<VarId>>()
+        } else {
+            projection.to_vec()
+        };
+        let activator = match self.state.activator {
+            Some(vi) => vi,
+            None => {
+                let vi = self.new_var();
+                // it's kept from elimination until `new_var` gives it to the user.
+                self.freeze(vi);
+                self.state.activator = Some(vi);
+                vi
+            }
+        };
+        // the vars in blocking clauses are kept from elimination during the iteration.
+        for vi in &projection {
+            self.freeze(*vi);
+        }
+        Ok(Models {
+            solver: self,
+            projection,
+            activator,
+            result: None,
+        })
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::{LitIF, SatSolverIF};
+    /// use splr::config::Config;
+    /// use splr::solver::{Certificate, Solver};
+    /// use splr::types::{CNFDescription, Lit};
+    ///
+    /// let cnf = CNFDescription {
+    ///     num_of_variables: 3,
+    ///     num_of_clauses: 2,
+    ///     pathname: None,
+    /// };
+    /// let mut s = Solver::new(&Config::default(), &cnf);
+    /// s.add_unchecked_clause(&mut vec![Lit::from_int(1), Lit::from_int(2)]);
+    /// s.add_unchecked_clause(&mut vec![Lit::from_int(-1), Lit::from_int(2)]);
+    /// assert_eq!(
+    ///     s.solve_with_assumptions(&[Lit::from_int(3), Lit::from_int(-2)]).unwrap(),
+    ///     Certificate::UNSATUnderAssumptions(vec![-2])
+    /// );
+    /// assert_eq!(
+    ///     s.solve_with_assumptions(&[Lit::from_int(-1), Lit::from_int(3)]).unwrap(),
+    ///     Certificate::SAT(vec![-1, 2, 3])
+    /// );
+    ///```
+    fn solve_with_assumptions(&mut self, assumptions: &[Lit]) -> SolverResult {
+        for l in assumptions {
+            self.restore_eliminated_var(l.vi());
+        }
         let Solver {
             ref mut asgs,
             ref mut cdb,
@@ -86,74 +352,44 @@ impl SatSolverIF for Solver {
         if cdb.check_size(state).is_err() {
             return Err(SolverException::OutOfMemory);
         }
-        // NOTE: splr doesn't deal with assumptions.
-        // s.root_level = 0;
         state.num_solved_vars = asgs.len();
+        state.stats[Stat::Solve] += 1;
+        state.reset_budget();
         state.progress_header();
         state.progress(cdb, vars, Some("initialization phase"));
         state.flush("loading...");
-        let use_pre_processor = true;
-        let use_pre_processing_eliminator = true;
-        if use_pre_processor {
-            state.flush("phasing...");
-            elim.activate();
-            elim.prepare(cdb, vars, true);
-            // run simple preprocessor
-            for vi in 1..vars.len() {
-                let v = &mut vars[vi];
-                if v.assign != BOTTOM {
-                    continue;
-                }
-                match (v.pos_occurs.len(), v.neg_occurs.len()) {
-                    (_, 0) => asgs.enqueue_null(v, TRUE),
-                    (0, _) => asgs.enqueue_null(v, FALSE),
-                    (p, m) if m * 10 < p => {
-                        v.phase = TRUE;
-                        elim.enqueue_var(vars, vi, false);
-                    }
-                    (p, m) if p * 10 < m => {
-                        v.phase = FALSE;
-                        elim.enqueue_var(vars, vi, false);
-                    }
-                    _ => (),
-                }
+        // Assumed vars must be neither fixed by the preprocessor nor eliminated.
+        // And the saved phases and learnt clauses should survive in the following calls.
+        if assumptions.is_empty()
+            && state.stats[Stat::Solve] == 1
+            && !state.preprocessed
+            && pre_process(asgs, cdb, elim, state, vars).is_err()
+        {
+            // Why inconsistent? Because the CNF contains a conflict, not an error!
+            // Or out of memory.
+            state.progress(cdb, vars, None);
+            state.ok = false;
+            if cdb.check_size(state).is_err() {
+                return Err(SolverException::OutOfMemory);
             }
-            if !state.use_elim || !use_pre_processing_eliminator {
-                elim.stop(cdb, vars);
-            }
-            // state.progress(cdb, vars, Some("phase-in"));
-        }
-        if state.use_elim && use_pre_processing_eliminator {
-            state.flush("simplifying...");
-            // if 20_000_000 < state.target.num_of_clauses {
-            //     state.elim_eliminate_grow_limit = 0;
-            //     state.elim_eliminate_loop_limit = 1_000_000;
-            //     state.elim_subsume_loop_limit = 2_000_000;
-            // }
-            if cdb.simplify(asgs, elim, state, vars).is_err() {
-                // Why inconsistent? Because the CNF contains a conflict, not an error!
-                // Or out of memory.
-                state.progress(cdb, vars, None);
+            cdb.certificate_add(&[], &[]);
+            return Ok(Certificate::UNSAT);
+        }
+        state.progress(cdb, vars, None);
+        if !assumptions.is_empty() {
+            if asgs.propagate(cdb, state, vars) != NULL_CLAUSE {
                 state.ok = false;
-                if cdb.check_size(state).is_err() {
-                    return Err(SolverException::OutOfMemory);
-                }
+                cdb.certificate_add(&[], &[]);
                 return Ok(Certificate::UNSAT);
             }
-            for v in &mut vars[1..] {
-                if v.assign != BOTTOM || v.is(Flag::ELIMINATED) {
-                    continue;
-                }
-                match (v.pos_occurs.len(), v.neg_occurs.len()) {
-                    (_, 0) => (),
-                    (0, _) => (),
-                    (p, m) if m * 10 < p => v.phase = TRUE,
-                    (p, m) if p * 10 < m => v.phase = FALSE,
-                    _ => (),
+            if !assume(asgs, cdb, state, vars, assumptions) {
+                if state.conflicts.is_empty() {
+                    cdb.certificate_add(&[], &[]);
                 }
+                cancel_assumptions(asgs, state, vars);
+                return Ok(failed_assumptions(state));
             }
         }
-        state.progress(cdb, vars, None);
         match search(asgs, cdb, elim, state, vars) {
             Ok(true) => {
                 state.progress(cdb, vars, None);
@@ -166,16 +402,33 @@ impl SatSolverIF for Solver {
                     }
                 }
                 elim.extend_model(&mut result);
-                asgs.cancel_until(vars, 0);
+                // the activator of `models` isn't a var of the problem.
+                if state.activator.is_some() {
+                    result.pop();
+                }
+                cancel_assumptions(asgs, state, vars);
                 Ok(Certificate::SAT(result))
             }
             Ok(false) => {
                 state.progress(cdb, vars, None);
-                asgs.cancel_until(vars, 0);
-                Ok(Certificate::UNSAT)
+                cancel_assumptions(asgs, state, vars);
+                Ok(failed_assumptions(state))
+            }
+            Err(_) if state.is_interrupted() => {
+                // The solver is still consistent; keep it reusable.
+                cancel_assumptions(asgs, state, vars);
+                state.progress(cdb, vars, Some("interrupted"));
+                // observers may request an interruption again in `progress`.
+                state.interrupt.clear();
+                Err(SolverException::Interrupted)
+            }
+            Err(_) if state.exhausted.is_some() => {
+                cancel_assumptions(asgs, state, vars);
+                state.progress(cdb, vars, Some("out of budget"));
+                Ok(Certificate::Unknown(state.exhausted.clone().unwrap()))
             }
             Err(_) => {
-                asgs.cancel_until(vars, 0);
+                cancel_assumptions(asgs, state, vars);
                 state.progress(cdb, vars, Some("ERROR"));
                 state.ok = false;
                 if cdb.check_size(state).is_err() {
@@ -191,6 +444,83 @@ impl SatSolverIF for Solver {
     /// # Examples
     ///
     /// ```
+    /// use splr::traits::{EliminatorIF, SatSolverIF};
+    /// use splr::config::Config;
+    /// use splr::solver::{Certificate, Solver};
+    /// use std::convert::TryFrom;
+    ///
+    /// let cnf = vec![vec![1, 2], vec![-1, 3], vec![-2, 3], vec![-3, 4], vec![-4, -3]];
+    /// let mut s = Solver::try_from((Config::default(), cnf)).unwrap();
+    /// assert!(!s.preprocess().unwrap());
+    /// assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+    ///
+    /// let mut s = Solver::try_from((Config::default(), vec![vec![1, 2], vec![-1, 2]])).unwrap();
+    /// assert!(s.preprocess().unwrap());
+    /// assert!(!s.elim.reconstruction_stack().is_empty());
+    /// assert_eq!(s.solve().unwrap(), Certificate::SAT(vec![-1, 2]));
+    ///```
+    fn preprocess(&mut self) -> Result<bool, SolverException> {
+        let Solver {
+            ref mut asgs,
+            ref mut cdb,
+            ref mut elim,
+            ref mut state,
+            ref mut vars,
+        } = self;
+        if !state.ok {
+            return Ok(false);
+        }
+        if cdb.check_size(state).is_err() {
+            return Err(SolverException::OutOfMemory);
+        }
+        if 0 < state.stats[Stat::Solve] || state.preprocessed {
+            return Ok(true);
+        }
+        state.progress_header();
+        state.progress(cdb, vars, Some("initialization phase"));
+        if pre_process(asgs, cdb, elim, state, vars).is_err() {
+            state.ok = false;
+            if cdb.check_size(state).is_err() {
+                return Err(SolverException::OutOfMemory);
+            }
+            cdb.certificate_add(&[], &[]);
+        }
+        state.progress(cdb, vars, None);
+        Ok(state.ok)
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::{LitIF, SatSolverIF};
+    /// use splr::config::Config;
+    /// use splr::solver::{Solver, Certificate};
+    /// use splr::types::{CNFDescription, Lit};
+    ///
+    /// let mut s = Solver::new(&Config::default(), &CNFDescription::default());
+    /// let v1 = s.new_var();
+    /// let v2 = s.new_var();
+    /// assert_eq!((v1, v2), (1, 2));
+    /// s.add_unchecked_clause(&mut vec![Lit::from_int(1), Lit::from_int(2)]);
+    /// s.add_unchecked_clause(&mut vec![Lit::from_int(-1)]);
+    /// assert_eq!(s.solve().unwrap(), Certificate::SAT(vec![-1, 2]));
+    ///```
+    fn new_var(&mut self) -> VarId {
+        // the activator of `models` is the last var and in no clause out of the iteration.
+        if let Some(vi) = self.state.activator.take() {
+            self.melt(vi);
+            return vi;
+        }
+        let vi = self.vars.len();
+        self.vars.push(Var::new(vi));
+        self.asgs.new_var(&self.vars);
+        self.cdb.new_var();
+        self.elim.new_var();
+        self.state.new_var();
+        vi
+    }
+    /// # Examples
+    ///
+    /// ```
     /// use splr::traits::SatSolverIF;
     /// use splr::config::Config;
     /// use splr::solver::Solver;
@@ -198,78 +528,61 @@ impl SatSolverIF for Solver {
     /// let config = Config::from("tests/sample.cnf");
     /// assert!(Solver::build(&config).is_ok());
     ///```
-    fn build(config: &Config) -> std::io::Result<Solver> {
+    fn build(config: &Config) -> Result<Solver, ParseError> {
         let fs = fs::File::open(&config.cnf_filename)?;
-        let mut rs = BufReader::new(fs);
-        let mut buf = String::new();
-        let mut nv: usize = 0;
-        let mut nc: usize = 0;
-        loop {
-            buf.clear();
-            match rs.read_line(&mut buf) {
-                Ok(0) => break,
-                Ok(_k) => {
-                    let mut iter = buf.split_whitespace();
-                    if iter.next() == Some("p") && iter.next() == Some("cnf") {
-                        if let Some(v) = iter.next().map(|s| s.parse::<usize>().ok().unwrap()) {
-                            if let Some(c) = iter.next().map(|s| s.parse::<usize>().ok().unwrap()) {
-                                nv = v;
-                                nc = c;
-                                break;
-                            }
-                        }
-                    }
-                    continue;
-                }
-                Err(e) => panic!("{}", e),
-            }
-        }
-        let cnf = CNFDescription {
-            num_of_variables: nv,
-            num_of_clauses: nc,
-            pathname: config.cnf_filename.to_str().unwrap().to_string(),
+        let pathname = config.cnf_filename.to_str().unwrap().to_string();
+        let proof: Option<Box<dyn ProofSinkIF>> = if config.use_certification {
+            let file = config.output_dirname.join(&config.proof_filename);
+            let format = if config.use_frat {
+                ProofFormat::FRAT
+            } else if config.use_binary_proof {
+                ProofFormat::Binary
+            } else {
+                ProofFormat::Text
+            };
+            Some(Box::new(ProofWriter::create(file, format)?))
+        } else {
+            None
         };
-        let mut s: Solver = Solver::new(config, &cnf);
-        loop {
-            buf.clear();
-            match rs.read_line(&mut buf) {
-                Ok(0) => break,
-                Ok(_) => {
-                    if buf.starts_with('c') {
-                        continue;
-                    }
-                    let iter = buf.split_whitespace();
-                    let mut v: Vec<Lit> = Vec::new();
-                    for s in iter {
-                        match s.parse::<i32>() {
-                            Ok(0) => break,
-                            Ok(val) => v.push(Lit::from_int(val)),
-                            Err(_) => (),
-                        }
-                    }
-                    if !v.is_empty() && s.add_unchecked_clause(&mut v) == None {
-                        s.state.ok = false;
-                    }
-                }
-                Err(e) => panic!("{}", e),
-            }
-        }
-        debug_assert_eq!(s.vars.len() - 1, nv);
-        Ok(s)
+        Solver::load(config, BufReader::new(fs), Some(pathname), proof)
+    }
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::SatSolverIF;
+    /// use splr::config::Config;
+    /// use splr::solver::{Certificate, Solver};
+    ///
+    /// let cnf = "p cnf 2 2\n1 2 0\n-1 0\n";
+    /// let mut s = Solver::from_reader(&Config::default(), cnf.as_bytes()).unwrap();
+    /// assert_eq!(s.solve().unwrap(), Certificate::SAT(vec![-1, 2]));
+    ///```
+    fn from_reader<R: BufRead>(config: &Config, reader: R) -> Result<Solver, ParseError> {
+        Solver::load(config, reader, None, None)
     }
     // renamed from clause_new
     fn add_unchecked_clause(&mut self, v: &mut Vec<Lit>) -> Option<ClauseId> {
+        if !self.state.ok {
+            return None;
+        }
+        for l in v.iter() {
+            self.restore_eliminated_var(l.vi());
+        }
         let Solver {
             ref mut asgs,
             ref mut cdb,
             ref mut elim,
+            ref mut state,
             ref mut vars,
-            ..
         } = self;
         debug_assert!(asgs.level() == 0);
-        if v.iter().any(|l| vars.assigned(*l) != BOTTOM) {
-            cdb.certificate_add(v);
-        }
+        let oid = cdb.certificate_original(v);
+        // keep the given clause to replace it with the simplified one in the certification.
+        let original = if cdb.certified.is_some() && v.iter().any(|l| vars.assigned(*l) != BOTTOM) {
+            Some(v.clone())
+        } else {
+            None
+        };
         v.sort_unstable();
         let mut j = 0;
         let mut l_ = NULL_LIT; // last literal; [x, x.negate()] means tautology.
@@ -277,6 +590,9 @@ impl SatSolverIF for Solver {
             let li = v[i];
             let sat = vars.assigned(li);
             if sat == TRUE || li.negate() == l_ {
+                if let Some(o) = original {
+                    cdb.certificate_delete(oid, &o);
+                }
                 return Some(NULL_CLAUSE);
             } else if sat != FALSE && li != l_ {
                 v[j] = li;
@@ -285,14 +601,32 @@ impl SatSolverIF for Solver {
             }
         }
         v.truncate(j);
+        let mut id = oid;
+        if let Some(o) = original {
+            let hints = if cdb.use_hints() {
+                antecedents(cdb, asgs, vars, v, &[], None, (&o, oid)).unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            id = cdb.certificate_add(v, &hints);
+            cdb.certificate_delete(oid, &o);
+        } else if v.is_empty() {
+            cdb.certificate_add(&[], &[]);
+        }
         match v.len() {
-            0 => None, // Empty clause is UNSAT.
+            0 => {
+                // Empty clause is UNSAT.
+                state.ok = false;
+                None
+            }
             1 => {
+                cdb.proof_unit[v[0].vi()] = id;
                 asgs.enqueue_null(&mut vars[v[0].vi()], v[0].lbool());
                 Some(NULL_CLAUSE)
             }
             _ => {
                 let cid = cdb.new_clause(&v, 0, false);
+                cdb.proof_id[cid as usize] = id;
                 elim.add_cid_occur(vars, cid, &mut cdb.clause[cid as usize], true);
                 Some(cid)
             }
@@ -300,6 +634,581 @@ impl SatSolverIF for Solver {
     }
 }
 
+impl TryFrom<(Config, Vec<Vec<i32>>)> for Solver {
+    /// `Ok(Certificate::UNSAT)` if the clauses are trivially inconsistent
+    type Error = SolverResult;
+    /// make a solver from clauses in the DIMACS-like integer notation, without the trailing zeros.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use splr::traits::SatSolverIF;
+    /// use splr::config::Config;
+    /// use splr::solver::{Certificate, Solver};
+    /// use std::convert::TryFrom;
+    ///
+    /// let mut s = Solver::try_from((Config::default(), vec![vec![1, 2], vec![-1]])).unwrap();
+    /// assert_eq!(s.solve().unwrap(), Certificate::SAT(vec![-1, 2]));
+    /// assert!(Solver::try_from((Config::default(), vec![vec![1], vec![-1]])).is_err());
+    ///```
+    fn try_from((config, vec): (Config, Vec<Vec<i32>>)) -> Result<Solver, SolverResult> {
+        let cnf = CNFDescription::from(vec.as_slice());
+        let mut s = Solver::new(&config, &cnf);
+        for c in &vec {
+            if c.contains(&0) {
+                return Err(Err(SolverException::InvalidLiteral));
+            }
+            let mut v = c.iter().map(|l| Lit::from_int(*l)).collect::<Vec<Lit>>();
+            if s.add_unchecked_clause(&mut v).is_none() {
+                return Err(Ok(Certificate::UNSAT));
+            }
+        }
+        Ok(s)
+    }
+}
+
+impl Solver {
+    /// add vars by `new_var` until `vi` can be used, taking back the activator of `models`.
+    pub(crate) fn reserve_var(&mut self, vi: VarId) {
+        while self.vars.len() <= vi || self.state.activator == Some(vi) {
+            self.new_var();
+        }
+    }
+    /// load a CNF in DIMACS format from `rs`.
+    /// In lenient mode, out-of-range vars are added and the number of clauses isn't checked.
+    /// `proof` replaces the default certification sink if given.
+    fn load<R: BufRead>(
+        config: &Config,
+        mut rs: R,
+        pathname: Option<String>,
+        mut proof: Option<Box<dyn ProofSinkIF>>,
+    ) -> Result<Solver, ParseError> {
+        let mut buf = String::new();
+        let mut line = 0;
+        let mut solver: Option<Solver> = None;
+        let mut found = 0;
+        let mut v: Vec<Lit> = Vec::new();
+        // the vars in `c ind` lines with their positions
+        let mut projection: Vec<(i32, usize, usize)> = Vec::new();
+        loop {
+            buf.clear();
+            if rs.read_line(&mut buf)? == 0 {
+                break;
+            }
+            line += 1;
+            let head = buf.trim_start();
+            // the vars to count models on, used in projected model counting.
+            // A line with other words is just a comment.
+            if let Some(vars) = head.strip_prefix("c ind ") {
+                let ind = vars
+                    .split_whitespace()
+                    .map(|token| {
+                        let column = token.as_ptr() as usize - buf.as_ptr() as usize + 1;
+                        token.parse::<i32>().map(|i| (i, line, column))
+                    })
+                    .collect::<Result<Vec<_>, _>>();
+                if let Ok(ind) = ind {
+                    projection.extend(ind.into_iter().take_while(|(i, _, _)| *i != 0));
+                }
+                continue;
+            }
+            if head.is_empty() || head.starts_with('c') {
+                continue;
+            }
+            // the end marker used in SATLIB
+            if head.starts_with('%') {
+                break;
+            }
+            let s = match solver {
+                Some(ref mut s) => s,
+                None => {
+                    let mut iter = head.split_whitespace();
+                    if iter.next() != Some("p") {
+                        return Err(ParseError::MissingHeader);
+                    }
+                    let format = iter.next();
+                    let mut num = || iter.next().and_then(|s| s.parse::<usize>().ok());
+                    match (format, num(), num()) {
+                        (Some("cnf"), Some(nv), Some(nc)) => {
+                            let cnf = CNFDescription {
+                                num_of_variables: nv,
+                                num_of_clauses: nc,
+                                pathname: pathname.clone(),
+                            };
+                            let mut s = Solver::new(config, &cnf);
+                            if proof.is_some() {
+                                s.cdb.certified = proof.take();
+                            }
+                            solver = Some(s);
+                            continue;
+                        }
+                        _ => return Err(ParseError::BadHeader { line }),
+                    }
+                }
+            };
+            for token in buf.split_whitespace() {
+                let column = token.as_ptr() as usize - buf.as_ptr() as usize + 1;
+                match token.parse::<i32>() {
+                    Ok(0) => {
+                        found += 1;
+                        if s.add_unchecked_clause(&mut v).is_none() {
+                            s.state.ok = false;
+                        }
+                        v.clear();
+                    }
+                    Ok(literal) => {
+                        let vi = literal.unsigned_abs() as usize;
+                        if s.state.num_vars < vi {
+                            if !config.use_lenient_parser {
+                                return Err(ParseError::OutOfRangeLiteral {
+                                    line,
+                                    column,
+                                    literal,
+                                });
+                            }
+                            s.reserve_var(vi);
+                        }
+                        v.push(Lit::from_int(literal));
+                    }
+                    Err(_) => return Err(ParseError::BadLiteral { line, column }),
+                }
+            }
+        }
+        let mut s = solver.ok_or(ParseError::MissingHeader)?;
+        // the last clause may lack the terminating zero.
+        if !v.is_empty() {
+            found += 1;
+            if s.add_unchecked_clause(&mut v).is_none() {
+                s.state.ok = false;
+            }
+        }
+        let expected = s.state.target.num_of_clauses;
+        if found != expected && !config.use_lenient_parser {
+            return Err(ParseError::ClauseCountMismatch { expected, found });
+        }
+        for (literal, line, column) in projection {
+            let vi = literal.unsigned_abs() as VarId;
+            if s.state.num_vars < vi {
+                if !config.use_lenient_parser {
+                    return Err(ParseError::OutOfRangeLiteral {
+                        line,
+                        column,
+                        literal,
+                    });
+                }
+                s.reserve_var(vi);
+            }
+            s.state.projection.push(vi);
+        }
+        s.state.projection.sort_unstable();
+        s.state.projection.dedup();
+        Ok(s)
+    }
+    /// bring an eliminated var and its clauses back to make it usable in new clauses or assumptions.
+    /// The eliminated vars in the clauses are brought back too.
+    pub(crate) fn restore_eliminated_var(&mut self, vi: VarId) {
+        let mut vars = vec![vi];
+        let mut clauses = Vec::new();
+        while let Some(vi) = vars.pop() {
+            if !self.vars[vi].is(Flag::ELIMINATED) {
+                continue;
+            }
+            self.vars[vi].turn_off(Flag::ELIMINATED);
+            self.asgs.insert_order(&self.vars, vi);
+            self.state.num_eliminated_vars -= 1;
+            for c in self.elim.take_eliminated_clauses(vi) {
+                vars.extend(
+                    c.iter()
+                        .map(|l| l.vi())
+                        .filter(|vj| self.vars[*vj].is(Flag::ELIMINATED)),
+                );
+                clauses.push(c);
+            }
+        }
+        for mut c in clauses {
+            if self.add_unchecked_clause(&mut c).is_none() {
+                self.state.ok = false;
+            }
+        }
+    }
+}
+
+/// backtracks to decision level `lv`, and notifies the external propagator.
+fn backtrack(asgs: &mut AssignStack, state: &mut State, vars: &mut [Var], lv: usize) {
+    if asgs.level() <= lv {
+        return;
+    }
+    asgs.cancel_until(vars, lv);
+    if let Some(p) = state.propagator.as_mut() {
+        let len = asgs.len();
+        state.propagator_trail = state.propagator_trail.min(len);
+        state.propagator_pending.retain(|i| *i < len);
+        p.notify_backtrack(lv);
+    }
+}
+
+/// notifies the external propagator of the new assignments on observed vars by decision level.
+fn notify_assignments(asgs: &AssignStack, state: &mut State, vars: &[Var]) {
+    let State {
+        ref mut propagator,
+        ref mut propagator_trail,
+        ..
+    } = state;
+    if let Some(p) = propagator {
+        let mut lits: Vec<Lit> = Vec::new();
+        let mut level = 0;
+        for l in &asgs.trail[*propagator_trail..] {
+            let v = &vars[l.vi()];
+            if !v.is(Flag::OBSERVED) {
+                continue;
+            }
+            if v.level != level && !lits.is_empty() {
+                p.notify_assignment(&lits, level);
+                lits.clear();
+            }
+            level = v.level;
+            lits.push(*l);
+        }
+        if !lits.is_empty() {
+            p.notify_assignment(&lits, level);
+        }
+        *propagator_trail = asgs.len();
+    }
+}
+
+/// runs the external propagator and unit propagation until a fixpoint, then lets it check
+/// the model if all vars are assigned. Returns a conflicting clause or `NULL_CLAUSE`.
+/// `Err` means that the problem is unsatisfiable, and `state.conflicts` holds the reason.
+fn propagate_externally(
+    asgs: &mut AssignStack,
+    cdb: &mut ClauseDB,
+    elim: &mut Eliminator,
+    state: &mut State,
+    vars: &mut [Var],
+) -> Result<ClauseId, SolverError> {
+    loop {
+        notify_assignments(asgs, state, vars);
+        let mut progress = false;
+        let lits = match state.propagator.as_mut() {
+            Some(p) => p.propagate(),
+            None => return Ok(NULL_CLAUSE),
+        };
+        for l in lits {
+            match vars.assigned(l) {
+                TRUE => (),
+                FALSE => {
+                    let mut reason = match state.propagator.as_mut() {
+                        Some(p) => p.reason(l),
+                        None => return Ok(NULL_CLAUSE),
+                    };
+                    let ci = add_clause_in_search(asgs, cdb, elim, state, vars, &mut reason, true)?;
+                    if ci != NULL_CLAUSE {
+                        return Ok(ci);
+                    }
+                    // the rest may be stale after backtracking.
+                    progress = true;
+                    break;
+                }
+                _ => {
+                    if !asgs.is_zero() {
+                        state.propagator_pending.push(asgs.len());
+                    }
+                    let lv = asgs.level();
+                    asgs.enqueue(&mut vars[l.vi()], l.lbool(), NULL_CLAUSE, lv)?;
+                    progress = true;
+                }
+            }
+        }
+        let ci = add_external_clauses(asgs, cdb, elim, state, vars, &mut progress)?;
+        if ci != NULL_CLAUSE {
+            return Ok(ci);
+        }
+        if !progress && state.num_vars <= asgs.len() + state.num_eliminated_vars {
+            let model = vars[1..]
+                .iter()
+                .map(|v| match v.assign {
+                    TRUE => v.index as i32,
+                    FALSE => -(v.index as i32),
+                    _ => 0,
+                })
+                .collect::<Vec<i32>>();
+            let accepted = match state.propagator.as_mut() {
+                Some(p) => p.check_model(&model),
+                None => true,
+            };
+            if !accepted {
+                let ci = add_external_clauses(asgs, cdb, elim, state, vars, &mut progress)?;
+                if ci != NULL_CLAUSE {
+                    return Ok(ci);
+                }
+            }
+        }
+        if !progress {
+            return Ok(NULL_CLAUSE);
+        }
+        let ci = asgs.propagate(cdb, state, vars);
+        if ci != NULL_CLAUSE {
+            return Ok(ci);
+        }
+    }
+}
+
+/// adds the clauses given by `ExternalPropagatorIF::add_clause`, and sets `added` if any.
+/// Returns a conflicting clause or `NULL_CLAUSE`.
+fn add_external_clauses(
+    asgs: &mut AssignStack,
+    cdb: &mut ClauseDB,
+    elim: &mut Eliminator,
+    state: &mut State,
+    vars: &mut [Var],
+    added: &mut bool,
+) -> Result<ClauseId, SolverError> {
+    while let Some(mut lits) = state.propagator.as_mut().and_then(|p| p.add_clause()) {
+        *added = true;
+        let ci = add_clause_in_search(asgs, cdb, elim, state, vars, &mut lits, false)?;
+        if ci != NULL_CLAUSE {
+            return Ok(ci);
+        }
+    }
+    Ok(NULL_CLAUSE)
+}
+
+/// adds a clause during search. If it's unit or conflicting, backtracks to the lowest level
+/// where it is so, and propagates the unit. Returns the clause if it's conflicting, or
+/// `NULL_CLAUSE`. `Err` means that it's conflicting under the assumptions or at level zero.
+fn add_clause_in_search(
+    asgs: &mut AssignStack,
+    cdb: &mut ClauseDB,
+    elim: &mut Eliminator,
+    state: &mut State,
+    vars: &mut [Var],
+    lits: &mut Vec<Lit>,
+    learnt: bool,
+) -> Result<ClauseId, SolverError> {
+    lits.sort_unstable();
+    lits.dedup();
+    if lits.windows(2).any(|w| w[0] == w[1].negate())
+        || lits
+            .iter()
+            .any(|l| vars.assigned(*l) == TRUE && vars[l.vi()].level == 0)
+    {
+        return Ok(NULL_CLAUSE);
+    }
+    lits.retain(|l| vars.assigned(*l) != FALSE || 0 < vars[l.vi()].level);
+    let root = state.root_level;
+    if lits.len() < 2 {
+        let l0 = match lits.first() {
+            Some(l) => *l,
+            None => {
+                state.conflicts.clear();
+                return Err(SolverError::Inconsistent);
+            }
+        };
+        backtrack(asgs, state, vars, root);
+        match vars.assigned(l0) {
+            TRUE => (),
+            FALSE => {
+                explain_externally(asgs, cdb, elim, state, vars);
+                analyze_final(asgs, cdb, state, vars, &[l0]);
+                return Err(SolverError::Inconsistent);
+            }
+            _ if root == 0 => asgs.uncheck_enqueue(vars, l0, NULL_CLAUSE),
+            // a fact found under assumptions is kept at the root level as a level zero var.
+            _ => asgs.enqueue(&mut vars[l0.vi()], l0.lbool(), NULL_CLAUSE, 0)?,
+        }
+        return Ok(NULL_CLAUSE);
+    }
+    // watch true literals, unassigned ones, then false ones at higher levels.
+    lits.sort_by_key(|l| match vars.assigned(*l) {
+        TRUE => (0, Reverse(0)),
+        FALSE => (2, Reverse(vars[l.vi()].level)),
+        _ => (1, Reverse(0)),
+    });
+    let cid = cdb.new_clause(lits, lits.len(), learnt);
+    elim.add_cid_occur(vars, cid, &mut cdb.clause[cid as usize], true);
+    let (l0, l1) = (lits[0], lits[1]);
+    if vars.assigned(l0) == TRUE || vars.assigned(l1) != FALSE {
+        return Ok(NULL_CLAUSE);
+    }
+    if vars.assigned(l0) == FALSE && vars[l0.vi()].level == vars[l1.vi()].level {
+        let lv = vars[l0.vi()].level;
+        backtrack(asgs, state, vars, lv.max(root));
+        return Ok(cid);
+    }
+    let lv = vars[l1.vi()].level;
+    backtrack(asgs, state, vars, lv.max(root));
+    if vars.assigned(l0) == FALSE {
+        return Ok(cid);
+    }
+    asgs.uncheck_enqueue(vars, l0, cid);
+    Ok(NULL_CLAUSE)
+}
+
+/// asks the external propagator the reasons of all the literals it propagated, for `analyze_final`.
+fn explain_externally(
+    asgs: &AssignStack,
+    cdb: &mut ClauseDB,
+    elim: &mut Eliminator,
+    state: &mut State,
+    vars: &mut [Var],
+) {
+    while let Some(i) = state.propagator_pending.last().copied() {
+        explain_external(asgs, cdb, elim, state, vars, i);
+    }
+}
+
+/// asks the external propagator the reason of `asgs.trail[i]` and makes it the reason clause.
+/// Returns `NULL_CLAUSE` if the reason is empty; the literal goes to `state.propagator_facts`
+/// then, to be added at level zero after the conflict.
+fn explain_external(
+    asgs: &AssignStack,
+    cdb: &mut ClauseDB,
+    elim: &mut Eliminator,
+    state: &mut State,
+    vars: &mut [Var],
+    i: usize,
+) -> ClauseId {
+    state.propagator_pending.retain(|j| *j != i);
+    let l = asgs.trail[i];
+    let mut lits = match state.propagator.as_mut() {
+        Some(p) => p.reason(l),
+        None => return NULL_CLAUSE,
+    };
+    lits.retain(|q| *q != l);
+    debug_assert!(lits.iter().all(|q| vars.assigned(*q) == FALSE));
+    if lits.is_empty() {
+        state.propagator_facts.push(l);
+        return NULL_CLAUSE;
+    }
+    lits.sort_by_key(|q| Reverse(vars[q.vi()].level));
+    lits.insert(0, l);
+    let cid = cdb.new_clause(&lits, lits.len(), true);
+    elim.add_cid_occur(vars, cid, &mut cdb.clause[cid as usize], true);
+    vars[l.vi()].reason = cid;
+    cid
+}
+
+/// assume literals as pseudo-decisions above level zero and set `state.root_level`;
+/// returns `false` if they are inconsistent.
+fn assume(
+    asgs: &mut AssignStack,
+    cdb: &mut ClauseDB,
+    state: &mut State,
+    vars: &mut [Var],
+    assumptions: &[Lit],
+) -> bool {
+    for l in assumptions {
+        if asgs.assume(vars, *l).is_err() {
+            // `l` was falsified by the former assumptions or a fact.
+            analyze_final(asgs, cdb, state, vars, &[*l]);
+            state.conflicts.push(l.negate());
+            return false;
+        }
+        let ci = asgs.propagate(cdb, state, vars);
+        if ci != NULL_CLAUSE {
+            analyze_final(asgs, cdb, state, vars, &cdb.clause[ci as usize].lits);
+            return false;
+        }
+    }
+    state.root_level = asgs.level();
+    true
+}
+
+/// make a certificate from the result of `analyze_final`.
+/// An empty set of responsible assumptions means the problem itself is unsatisfiable.
+fn failed_assumptions(state: &mut State) -> Certificate {
+    if state.conflicts.is_empty() {
+        // refuted at level zero; no clause added later can make it satisfiable.
+        state.ok = false;
+        Certificate::UNSAT
+    } else {
+        Certificate::UNSATUnderAssumptions(
+            state
+                .conflicts
+                .iter()
+                .map(|l| l.negate().to_i32())
+                .collect(),
+        )
+    }
+}
+
+/// backjump to level zero, keeping the facts found under assumptions.
+fn cancel_assumptions(asgs: &mut AssignStack, state: &mut State, vars: &mut [Var]) {
+    if !asgs.is_zero() {
+        let facts = asgs.trail[asgs.num_at(0)..]
+            .iter()
+            .filter(|l| vars[l.vi()].level == 0)
+            .cloned()
+            .collect::<Vec<Lit>>();
+        backtrack(asgs, state, vars, 0);
+        for l in &facts {
+            asgs.enqueue_null(&mut vars[l.vi()], l.lbool());
+        }
+    }
+    state.root_level = 0;
+}
+
+/// runs the simple preprocessor to set phases, then clause subsumption and variable elimination.
+fn pre_process(
+    asgs: &mut AssignStack,
+    cdb: &mut ClauseDB,
+    elim: &mut Eliminator,
+    state: &mut State,
+    vars: &mut [Var],
+) -> MaybeInconsistent {
+    state.preprocessed = true;
+    state.flush("phasing...");
+    elim.activate();
+    elim.prepare(cdb, vars, true);
+    // run simple preprocessor
+    for vi in 1..vars.len() {
+        let v = &mut vars[vi];
+        if v.assign != BOTTOM {
+            continue;
+        }
+        // Pure literals aren't fixed here, because clauses added later may refute them.
+        // The eliminator will remove them with the reconstruction info instead.
+        match (v.pos_occurs.len(), v.neg_occurs.len()) {
+            (_, 0) => {
+                v.phase = TRUE;
+                elim.enqueue_var(vars, vi, false);
+            }
+            (0, _) => {
+                v.phase = FALSE;
+                elim.enqueue_var(vars, vi, false);
+            }
+            (p, m) if m * 10 < p => {
+                v.phase = TRUE;
+                elim.enqueue_var(vars, vi, false);
+            }
+            (p, m) if p * 10 < m => {
+                v.phase = FALSE;
+                elim.enqueue_var(vars, vi, false);
+            }
+            _ => (),
+        }
+    }
+    if !state.use_elim {
+        elim.stop(cdb, vars);
+        return Ok(());
+    }
+    state.flush("simplifying...");
+    cdb.simplify(asgs, elim, state, vars)?;
+    for v in &mut vars[1..] {
+        if v.assign != BOTTOM || v.is(Flag::ELIMINATED) {
+            continue;
+        }
+        match (v.pos_occurs.len(), v.neg_occurs.len()) {
+            (_, 0) => (),
+            (0, _) => (),
+            (p, m) if m * 10 < p => v.phase = TRUE,
+            (p, m) if p * 10 < m => v.phase = FALSE,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
 /// main loop; returns `true` for SAT, `false` for UNSAT.
 fn search(
     asgs: &mut AssignStack,
@@ -312,15 +1221,30 @@ fn search(
     let mut a_decision_was_made = false;
     state.restart_update_luby();
     loop {
-        let ci = asgs.propagate(cdb, state, vars);
+        if state.is_interrupted() || state.is_over_budget() {
+            return Err(SolverError::Inconsistent);
+        }
+        let mut ci = asgs.propagate(cdb, state, vars);
         state.stats[Stat::Propagation] += 1;
+        if ci == NULL_CLAUSE && state.propagator.is_some() {
+            match propagate_externally(asgs, cdb, elim, state, vars) {
+                Ok(c) => ci = c,
+                Err(_) => {
+                    if state.conflicts.is_empty() {
+                        cdb.certificate_add(&[], &[]);
+                    }
+                    return Ok(false);
+                }
+            }
+        }
         if ci == NULL_CLAUSE {
             if state.num_vars <= asgs.len() + state.num_eliminated_vars {
                 return Ok(true);
             }
             // DYNAMIC FORCING RESTART
             if state.force_restart(&mut conflict_c) {
-                asgs.cancel_until(vars, state.root_level);
+                let root = state.root_level;
+                backtrack(asgs, state, vars, root);
             } else if asgs.level() == 0 {
                 if cdb.simplify(asgs, elim, state, vars).is_err() {
                     debug_assert!(false, "interal error by simplify");
@@ -329,9 +1253,21 @@ fn search(
                 state.num_solved_vars = asgs.len();
             }
             if !asgs.remains() {
-                let vi = asgs.select_var(&vars);
-                let p = vars[vi].phase;
-                asgs.uncheck_assume(vars, Lit::from_var(vi, p));
+                let decision = state
+                    .propagator
+                    .as_mut()
+                    .and_then(|p| p.decide())
+                    .filter(|l| {
+                        vars[l.vi()].assign == BOTTOM && !vars[l.vi()].is(Flag::ELIMINATED)
+                    });
+                let l = match decision {
+                    Some(l) => l,
+                    None => {
+                        let vi = asgs.select_var(&vars);
+                        Lit::from_var(vi, vars[vi].phase)
+                    }
+                };
+                asgs.uncheck_assume(vars, l);
                 state.stats[Stat::Decision] += 1;
                 a_decision_was_made = true;
             }
@@ -344,10 +1280,40 @@ fn search(
                 state.stats[Stat::NoDecisionConflict] += 1;
             }
             if asgs.level() == state.root_level {
-                analyze_final(asgs, state, vars, &cdb.clause[ci as usize]);
+                explain_externally(asgs, cdb, elim, state, vars);
+                analyze_final(asgs, cdb, state, vars, &cdb.clause[ci as usize].lits);
+                if state.conflicts.is_empty() {
+                    // refuted without assumptions; close the proof with the empty clause.
+                    let hints = if state.root_level == 0 && cdb.use_hints() {
+                        antecedents(
+                            cdb,
+                            asgs,
+                            vars,
+                            &[],
+                            &[],
+                            None,
+                            (&cdb.clause[ci as usize].lits, cdb.proof_id[ci as usize]),
+                        )
+                        .unwrap_or_default()
+                    } else {
+                        Vec::new()
+                    };
+                    cdb.certificate_add(&[], &hints);
+                }
                 return Ok(false);
             }
             handle_conflict_path(asgs, cdb, elim, state, vars, ci)?;
+            for l in std::mem::take(&mut state.propagator_facts) {
+                add_clause_in_search(asgs, cdb, elim, state, vars, &mut vec![l], true)?;
+            }
+            // measuring memory is too heavy to do at every conflict; check it every 1024 conflicts.
+            if 0 < state.budget_memory
+                && state.stats[Stat::Conflict] & 0x3FF == 0
+                && state.budget_memory < cdb.memory_usage() + vars.memory_usage()
+            {
+                state.exhausted = Some(Budget::Memory);
+                return Err(SolverError::Inconsistent);
+            }
         }
     }
 }
@@ -367,20 +1333,48 @@ fn handle_conflict_path(
     state.restart_update_asg(asgs.len());
     // DYNAMIC BLOCKING RESTART
     state.block_restart(asgs, tn_confl);
-    let bl = analyze(asgs, cdb, state, vars, ci);
+    let bl = analyze(asgs, cdb, elim, state, vars, ci);
+    let hints = if cdb.use_hints() {
+        antecedents(
+            cdb,
+            asgs,
+            vars,
+            &state.new_learnt,
+            &state.bi_clause_hints,
+            None,
+            (&cdb.clause[ci as usize].lits, cdb.proof_id[ci as usize]),
+        )
+        .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let root = state.root_level;
+    backtrack(asgs, state, vars, bl.max(root));
     let new_learnt = &mut state.new_learnt;
-    asgs.cancel_until(vars, bl.max(state.root_level));
     let learnt_len = new_learnt.len();
     if learnt_len == 1 {
         // dump to certified even if it's a literal.
-        cdb.certificate_add(new_learnt);
-        asgs.uncheck_enqueue(vars, new_learnt[0], NULL_CLAUSE);
+        cdb.proof_unit[new_learnt[0].vi()] = cdb.certificate_add(new_learnt, &hints);
+        if state.root_level == 0 {
+            asgs.uncheck_enqueue(vars, new_learnt[0], NULL_CLAUSE);
+        } else {
+            // a fact found under assumptions is kept at the root level as a level zero var.
+            let l0 = new_learnt[0];
+            asgs.enqueue(&mut vars[l0.vi()], l0.lbool(), NULL_CLAUSE, 0)?;
+        }
+        for o in &mut state.observers {
+            o.on_learnt(new_learnt, 1);
+        }
     } else {
         state.stats[Stat::Learnt] += 1;
         let lbd = vars.compute_lbd(&new_learnt, &mut state.lbd_temp);
         let l0 = new_learnt[0];
+        cdb.proof_hints = hints;
         let cid = cdb.attach(state, vars, lbd);
         elim.add_cid_occur(vars, cid, &mut cdb.clause[cid as usize], true);
+        for o in &mut state.observers {
+            o.on_learnt(&cdb.clause[cid as usize].lits, lbd);
+        }
         state.c_lvl.update(bl as f64);
         state.b_lvl.update(lbd as f64);
         if lbd <= 2 {
@@ -475,9 +1469,11 @@ fn adapt_parameters(
     }
     if nconflict == switch {
         state.flush("exhaustive eliminator activated...");
-        asgs.cancel_until(vars, 0);
+        let root = state.root_level;
+        backtrack(asgs, state, vars, root);
         state.adapt_strategy(cdb);
-        if state.use_elim {
+        // Eliminator works only at level zero, namely without assumptions.
+        if state.use_elim && state.root_level == 0 {
             cdb.reset(state.co_lbd_bound);
             elim.activate();
             cdb.simplify(asgs, elim, state, vars)?;
@@ -497,13 +1493,13 @@ fn adapt_parameters(
 fn analyze(
     asgs: &mut AssignStack,
     cdb: &mut ClauseDB,
+    elim: &mut Eliminator,
     state: &mut State,
     vars: &mut [Var],
     confl: ClauseId,
 ) -> usize {
-    let learnt = &mut state.new_learnt;
-    learnt.clear();
-    learnt.push(0);
+    state.new_learnt.clear();
+    state.new_learnt.push(0);
     let dl = asgs.level();
     let mut cid = confl;
     let mut p = NULL_LIT;
@@ -512,79 +1508,87 @@ fn analyze(
     state.last_dl.clear();
     loop {
         // println!("analyze {}", p.int());
-        unsafe {
-            debug_assert_ne!(cid, NULL_CLAUSE);
-            let c = &mut cdb.clause[cid as usize] as *mut Clause;
-            debug_assert!(!(*c).is(Flag::DEAD));
-            if (*c).is(Flag::LEARNT) {
-                cdb.bump_activity(&mut state.cla_inc, cid);
-                if 2 < (*c).rank {
-                    let nlevels = vars.compute_lbd(&(*c).lits, &mut state.lbd_temp);
-                    if nlevels + 1 < (*c).rank {
-                        if (*c).rank <= state.lbd_frozen_clause {
-                            (*c).turn_on(Flag::JUST_USED);
-                        }
-                        if state.use_chan_seok && nlevels < state.co_lbd_bound {
-                            (*c).turn_off(Flag::LEARNT);
-                            cdb.num_learnt -= 1;
-                        } else {
-                            (*c).rank = nlevels;
+        // an external fact resolves `p` away without any antecedent.
+        if cid != NULL_CLAUSE {
+            unsafe {
+                let c = &mut cdb.clause[cid as usize] as *mut Clause;
+                debug_assert!(!(*c).is(Flag::DEAD));
+                if (*c).is(Flag::LEARNT) {
+                    cdb.bump_activity(&mut state.cla_inc, cid);
+                    if 2 < (*c).rank {
+                        let nlevels = vars.compute_lbd(&(*c).lits, &mut state.lbd_temp);
+                        if nlevels + 1 < (*c).rank {
+                            if (*c).rank <= state.lbd_frozen_clause {
+                                (*c).turn_on(Flag::JUST_USED);
+                            }
+                            if state.use_chan_seok && nlevels < state.co_lbd_bound {
+                                (*c).turn_off(Flag::LEARNT);
+                                cdb.num_learnt -= 1;
+                            } else {
+                                (*c).rank = nlevels;
+                            }
                         }
                     }
                 }
-            }
-            if p != NULL_LIT && (*c).lits.len() == 2 && (*c).lits[1] == p {
-                (*c).lits.swap(0, 1);
-            }
-            // println!("- handle {}", cid.fmt());
-            for q in &(*c).lits[((p != NULL_LIT) as usize)..] {
-                let vi = q.vi();
-                vars.bump_activity(&mut state.var_inc, vi);
-                asgs.update_order(vars, vi);
-                let v = &mut vars[vi];
-                let lvl = v.level;
-                debug_assert!(!v.is(Flag::ELIMINATED));
-                debug_assert!(v.assign != BOTTOM);
-                if 0 < lvl && !state.an_seen[vi] {
-                    state.an_seen[vi] = true;
-                    if dl <= lvl {
-                        // println!("- flag for {} which level is {}", q.int(), lvl);
-                        path_cnt += 1;
-                        if v.reason != NULL_CLAUSE && cdb.clause[v.reason as usize].is(Flag::LEARNT)
-                        {
-                            state.last_dl.push(*q);
+                if p != NULL_LIT && (*c).lits.len() == 2 && (*c).lits[1] == p {
+                    (*c).lits.swap(0, 1);
+                }
+                // println!("- handle {}", cid.fmt());
+                for q in &(*c).lits[((p != NULL_LIT) as usize)..] {
+                    let vi = q.vi();
+                    vars.bump_activity(&mut state.var_inc, vi);
+                    asgs.update_order(vars, vi);
+                    let v = &mut vars[vi];
+                    let lvl = v.level;
+                    debug_assert!(!v.is(Flag::ELIMINATED));
+                    debug_assert!(v.assign != BOTTOM);
+                    if 0 < lvl && !state.an_seen[vi] {
+                        state.an_seen[vi] = true;
+                        if dl <= lvl {
+                            // println!("- flag for {} which level is {}", q.int(), lvl);
+                            path_cnt += 1;
+                            if v.reason != NULL_CLAUSE
+                                && cdb.clause[v.reason as usize].is(Flag::LEARNT)
+                            {
+                                state.last_dl.push(*q);
+                            }
+                        } else {
+                            // println!("- push {} to learnt, which level is {}", q.int(), lvl);
+                            state.new_learnt.push(*q);
                         }
                     } else {
-                        // println!("- push {} to learnt, which level is {}", q.int(), lvl);
-                        learnt.push(*q);
+                        // if !state.an_seen[vi] {
+                        //     println!("- ignore {} because it was flagged", q.int());
+                        // } else {
+                        //     println!("- ignore {} because its level is {}", q.int(), lvl);
+                        // }
                     }
-                } else {
-                    // if !state.an_seen[vi] {
-                    //     println!("- ignore {} because it was flagged", q.int());
-                    // } else {
-                    //     println!("- ignore {} because its level is {}", q.int(), lvl);
-                    // }
                 }
             }
-            // set the index of the next literal to ti
-            while !state.an_seen[asgs.trail[ti].vi()] {
-                // println!("- skip {} because it isn't flagged", asgs.trail[ti].int());
-                ti -= 1;
-            }
-            p = asgs.trail[ti];
-            let next_vi = p.vi();
-            cid = vars[next_vi].reason;
-            // println!("- move to flagged {}, which reason is {}; num path: {}",
-            //          next_vi, path_cnt - 1, cid.fmt());
-            state.an_seen[next_vi] = false;
-            path_cnt -= 1;
-            if path_cnt <= 0 {
-                break;
-            }
+        }
+        // set the index of the next literal to ti
+        while !state.an_seen[asgs.trail[ti].vi()] {
+            // println!("- skip {} because it isn't flagged", asgs.trail[ti].int());
             ti -= 1;
         }
+        p = asgs.trail[ti];
+        let next_vi = p.vi();
+        cid = vars[next_vi].reason;
+        // println!("- move to flagged {}, which reason is {}; num path: {}",
+        //          next_vi, path_cnt - 1, cid.fmt());
+        state.an_seen[next_vi] = false;
+        path_cnt -= 1;
+        if path_cnt <= 0 {
+            break;
+        }
+        if cid == NULL_CLAUSE && state.propagator_pending.binary_search(&ti).is_ok() {
+            // the external propagator explains a literal only when it's reached here.
+            cid = explain_external(asgs, cdb, elim, state, vars, ti);
+        }
+        debug_assert!(cid != NULL_CLAUSE || state.propagator_facts.contains(&p));
+        ti -= 1;
     }
-    learnt[0] = p.negate();
+    state.new_learnt[0] = p.negate();
     // println!("- appending {}, the result is {:?}", learnt[0].int(), vec2int(learnt));
     simplify_learnt(asgs, cdb, state, vars)
 }
@@ -610,8 +1614,15 @@ fn simplify_learnt(
         vars[l.vi()].reason == NULL_CLAUSE
             || !redundant_lit(cdb, vars, an_seen, *l, &mut to_clear, &levels)
     });
+    state.bi_clause_hints.clear();
     if new_learnt.len() < 30 {
-        minimize_with_bi_clauses(cdb, vars, &mut state.lbd_temp, new_learnt);
+        minimize_with_bi_clauses(
+            cdb,
+            vars,
+            &mut state.lbd_temp,
+            new_learnt,
+            &mut state.bi_clause_hints,
+        );
     }
     // glucose heuristics
     let lbd = vars.compute_lbd(new_learnt, &mut state.lbd_temp);
@@ -680,42 +1691,59 @@ fn redundant_lit(
     true
 }
 
-fn analyze_final(asgs: &AssignStack, state: &mut State, vars: &[Var], c: &Clause) {
-    let mut seen = vec![false; state.num_vars + 1];
-    state.conflicts.clear();
-    if asgs.level() == 0 {
+/// collect the negations of the assumptions, which `lits` are derived from, into `state.conflicts`.
+fn analyze_final(
+    asgs: &AssignStack,
+    cdb: &ClauseDB,
+    state: &mut State,
+    vars: &[Var],
+    lits: &[Lit],
+) {
+    let State {
+        ref mut conflicts,
+        ref mut an_seen,
+        ref propagator_facts,
+        ..
+    } = state;
+    conflicts.clear();
+    if asgs.is_zero() {
         return;
     }
-    for l in &c.lits {
+    for l in lits {
         let vi = l.vi();
         if 0 < vars[vi].level {
-            state.an_seen[vi] = true;
+            an_seen[vi] = true;
         }
     }
-    let end = if asgs.level() <= state.root_level {
-        asgs.len()
-    } else {
-        asgs.num_at(state.root_level)
-    };
-    for l in &asgs.trail[asgs.num_at(0)..end] {
+    for l in asgs.trail[asgs.num_at(0)..].iter().rev() {
         let vi = l.vi();
-        if seen[vi] {
-            if vars[vi].reason == NULL_CLAUSE {
-                state.conflicts.push(l.negate());
+        if an_seen[vi] {
+            let reason = vars[vi].reason;
+            if reason == NULL_CLAUSE {
+                if !propagator_facts.contains(l) {
+                    conflicts.push(l.negate());
+                }
             } else {
-                for l in &c.lits[(c.lits.len() != 2) as usize..] {
-                    let vi = l.vi();
-                    if 0 < vars[vi].level {
-                        seen[vi] = true;
+                for q in &cdb.clause[reason as usize].lits {
+                    let vj = q.vi();
+                    if 0 < vars[vj].level {
+                        an_seen[vj] = true;
                     }
                 }
             }
+            an_seen[vi] = false;
         }
-        seen[vi] = false;
     }
 }
 
-fn minimize_with_bi_clauses(cdb: &ClauseDB, vars: &[Var], temp: &mut [usize], vec: &mut Vec<Lit>) {
+/// removes literals implied by `vec[0]` through binary clauses; the ids of them go to `hints`.
+fn minimize_with_bi_clauses(
+    cdb: &ClauseDB,
+    vars: &[Var],
+    temp: &mut [usize],
+    vec: &mut Vec<Lit>,
+    hints: &mut Vec<(VarId, ProofId)>,
+) {
     let nlevels = vars.compute_lbd(vec, temp);
     if 6 < nlevels {
         return;
@@ -737,6 +1765,9 @@ fn minimize_with_bi_clauses(cdb: &ClauseDB, vars: &[Var], temp: &mut [usize], ve
         if temp[vi] == key && vars.assigned(other) == TRUE {
             nsat += 1;
             temp[vi] -= 1;
+            if cdb.use_hints() {
+                hints.push((vi, cdb.proof_id[w.c as usize]));
+            }
         }
     }
     if 0 < nsat {
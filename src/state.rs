@@ -11,6 +11,8 @@ use std::fmt;
 use std::io::{stdout, Write};
 use std::ops::{Index, IndexMut};
 use std::path::Path;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::Arc;
 use std::time::SystemTime;
 
 /// A collection of named search heuristics
@@ -117,9 +119,36 @@ pub enum Stat {
     NumBinLearnt,          // the number of binary learnt clauses
     NumLBD2,               // the number of clauses which LBD is 2
     Stagnation,            // the number of stagnation
+    Solve,                 // the number of `solve` invocations
     EndOfStatIndex,        // Don't use this dummy.
 }
 
+impl Stat {
+    /// names used in exported statistics
+    pub const NAMES: [&'static str; Stat::EndOfStatIndex as usize] = [
+        "conflict",
+        "decision",
+        "restart",
+        "restart_record",
+        "block_restart",
+        "block_restart_record",
+        "learnt",
+        "no_decision_conflict",
+        "propagation",
+        "reduction",
+        "sat_clause_elimination",
+        "exhaustive_elimination",
+        "assign",
+        "solved_record",
+        "sum_lbd",
+        "num_bin",
+        "num_bin_learnt",
+        "num_lbd2",
+        "stagnation",
+        "solve",
+    ];
+}
+
 impl Index<Stat> for [usize] {
     type Output = usize;
     fn index(&self, i: Stat) -> &usize {
@@ -133,6 +162,46 @@ impl IndexMut<Stat> for [usize] {
     }
 }
 
+/// Resources which limit a `solve` call
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Budget {
+    Conflict,
+    Propagation,
+    Decision,
+    Memory,
+}
+
+/// A cloneable flag to stop a running solver from another thread or a signal handler.
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    /// request the solver to stop.
+    pub fn interrupt(&self) {
+        self.0.store(true, atomic::Ordering::Relaxed);
+    }
+    /// return `true` if an interruption was requested.
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(atomic::Ordering::Relaxed)
+    }
+    /// withdraw the request.
+    pub fn clear(&self) {
+        self.0.store(false, atomic::Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for dyn ObserverIF {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observer")
+    }
+}
+
+impl fmt::Debug for dyn ExternalPropagatorIF {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ExternalPropagator")
+    }
+}
+
 /// Data storage for `Solver`
 #[derive(Debug)]
 pub struct State {
@@ -191,7 +260,33 @@ pub struct State {
     /// MISC
     pub config: Config,
     pub ok: bool,
+    /// whether the preprocessing phase has run by `solve` or `preprocess`
+    pub preprocessed: bool,
+    /// the vars given by `c ind` lines, on which models are counted
+    pub projection: Vec<VarId>,
+    /// the last var, kept for the activation literal of `Solver::models`, which no model contains
+    pub activator: Option<VarId>,
     pub time_limit: f64,
+    pub interrupt: InterruptHandle,
+    /// BUDGETS for a `solve` call; 0 for no limit
+    pub budget_conflict: usize,
+    pub budget_propagation: usize,
+    pub budget_decision: usize,
+    /// in bytes
+    pub budget_memory: usize,
+    /// the numbers of conflicts, propagations and decisions at the start of `solve`
+    pub budget_origin: (usize, usize, usize),
+    /// the budget which stopped the last `solve`
+    pub exhausted: Option<Budget>,
+    pub observers: Vec<Box<dyn ObserverIF>>,
+    /// EXTERNAL PROPAGATOR
+    pub propagator: Option<Box<dyn ExternalPropagatorIF>>,
+    /// the number of assignments on the trail checked for notification
+    pub propagator_trail: usize,
+    /// the positions on the trail of the literals propagated externally without reasons
+    pub propagator_pending: Vec<usize>,
+    /// the literals propagated externally with empty reasons, to be added at level zero
+    pub propagator_facts: Vec<Lit>,
     pub next_reduction: usize, // renamed from `nbclausesbeforereduce`
     pub next_restart: usize,
     pub cur_restart: usize,
@@ -209,6 +304,8 @@ pub struct State {
     pub model: Vec<Lbool>,
     pub conflicts: Vec<Lit>,
     pub new_learnt: Vec<Lit>,
+    /// binary clauses used in minimizing `new_learnt`, for certifications
+    pub bi_clause_hints: Vec<(VarId, ProofId)>,
     pub an_seen: Vec<bool>,
     pub lbd_temp: Vec<usize>,
     pub last_dl: Vec<Lit>,
@@ -340,7 +437,22 @@ impl Default for State {
             elim_subsume_loop_limit: 2_000_000,
             config: Config::default(),
             ok: true,
+            preprocessed: false,
+            projection: Vec::new(),
+            activator: None,
             time_limit: 0.0,
+            interrupt: InterruptHandle::default(),
+            budget_conflict: 0,
+            budget_propagation: 0,
+            budget_decision: 0,
+            budget_memory: 0,
+            budget_origin: (0, 0, 0),
+            exhausted: None,
+            observers: Vec::new(),
+            propagator: None,
+            propagator_trail: 0,
+            propagator_pending: Vec::new(),
+            propagator_facts: Vec::new(),
             next_reduction: 1000,
             next_restart: 100,
             cur_restart: 1,
@@ -358,11 +470,12 @@ impl Default for State {
             model: Vec::new(),
             conflicts: Vec::new(),
             new_learnt: Vec::new(),
+            bi_clause_hints: Vec::new(),
             an_seen: Vec::new(),
             lbd_temp: Vec::new(),
             last_dl: Vec::new(),
             start: SystemTime::now(),
-            use_progress: true,
+            use_progress: false,
             progress_cnt: 0,
             progress_log: false,
             record: ProgressRecord::default(),
@@ -374,16 +487,14 @@ impl Default for State {
 impl StateIF for State {
     fn new(config: &Config, mut cnf: CNFDescription) -> State {
         let mut state = State::default();
-        cnf.pathname = if cnf.pathname == "" {
-            "--".to_string()
-        } else {
-            Path::new(&cnf.pathname)
+        cnf.pathname = cnf.pathname.map(|p| {
+            Path::new(&p)
                 .file_name()
                 .unwrap()
                 .to_os_string()
                 .into_string()
                 .unwrap()
-        };
+        });
         state.num_vars = cnf.num_of_variables;
         state.adaptive_restart = !config.without_adaptive_restart;
         state.use_adapt_strategy = !config.without_adaptive_strategy;
@@ -405,9 +516,18 @@ impl StateIF for State {
         state.lbd_temp = vec![0; cnf.num_of_variables + 1];
         state.target = cnf;
         state.time_limit = config.timeout;
+        state.budget_conflict = config.conflict_budget;
+        state.budget_propagation = config.propagation_budget;
+        state.budget_decision = config.decision_budget;
+        state.budget_memory = config.memory_budget * 1024 * 1024;
         state.config = config.clone();
         state
     }
+    fn new_var(&mut self) {
+        self.num_vars += 1;
+        self.an_seen.push(false);
+        self.lbd_temp.push(0);
+    }
     fn is_timeout(&self) -> bool {
         if self.time_limit == 0.0 {
             return false;
@@ -417,6 +537,29 @@ impl StateIF for State {
             Err(_) => false,
         }
     }
+    fn is_interrupted(&self) -> bool {
+        self.interrupt.is_interrupted()
+    }
+    fn reset_budget(&mut self) {
+        self.budget_origin = (
+            self.stats[Stat::Conflict],
+            self.stats[Stat::Propagation],
+            self.stats[Stat::Decision],
+        );
+        self.exhausted = None;
+    }
+    fn is_over_budget(&mut self) -> bool {
+        let (c, p, d) = self.budget_origin;
+        let over = |budget: usize, origin: usize, now: usize| 0 < budget && origin + budget <= now;
+        if over(self.budget_conflict, c, self.stats[Stat::Conflict]) {
+            self.exhausted = Some(Budget::Conflict);
+        } else if over(self.budget_propagation, p, self.stats[Stat::Propagation]) {
+            self.exhausted = Some(Budget::Propagation);
+        } else if over(self.budget_decision, d, self.stats[Stat::Decision]) {
+            self.exhausted = Some(Budget::Decision);
+        }
+        self.exhausted.is_some()
+    }
     fn adapt_strategy(&mut self, cdb: &mut ClauseDB) {
         if !self.use_adapt_strategy || self.strategy != SearchStrategy::Initial {
             return;
@@ -463,6 +606,11 @@ impl StateIF for State {
         }
         if self.strategy == SearchStrategy::Initial {
             self.strategy = SearchStrategy::Generic;
+        }
+        for o in &mut self.observers {
+            o.on_strategy_change(&self.strategy);
+        }
+        if self.strategy == SearchStrategy::Generic {
             return;
         }
         if self.use_chan_seok {
@@ -509,22 +657,17 @@ impl StateIF for State {
     /// `mes` should be shorter than or equal to 9, or 8 + a delimiter.
     #[allow(clippy::cyclomatic_complexity)]
     fn progress(&mut self, cdb: &ClauseDB, vars: &[Var], mes: Option<&str>) {
-        if !self.use_progress {
-            return;
-        }
-        if self.progress_log {
-            self.dump(cdb, vars);
+        if !self.use_progress && self.observers.is_empty() {
             return;
         }
         let nv = vars.len() - 1;
         let fixed = self.num_solved_vars;
         let sum = fixed + self.num_eliminated_vars;
         self.progress_cnt += 1;
-        print!("\x1B[8A\x1B[1G");
         let count = self.stats[Stat::Conflict];
         let ave = self.stats[Stat::SumLBD] as f64 / count as f64;
-        println!("\x1B[2K{}", self);
-        println!(
+        let mut lines = vec![format!("\x1B[2K{}", self)];
+        lines.push(format!(
             "\x1B[2K #conflict:{}, #decision:{}, #propagate:{} ",
             i!(
                 "{:>11}",
@@ -544,8 +687,8 @@ impl StateIF for State {
                 LogUsizeId::Propagate,
                 self.stats[Stat::Propagation]
             ),
-        );
-        println!(
+        ));
+        lines.push(format!(
             "\x1B[2K  Assignment|#rem:{}, #fix:{}, #elm:{}, prg%:{} ",
             im!("{:>9}", self.record, LogUsizeId::Remain, nv - sum),
             im!("{:>9}", self.record, LogUsizeId::Fixed, fixed),
@@ -561,8 +704,8 @@ impl StateIF for State {
                 LogF64Id::Progress,
                 (sum as f64) / (nv as f64) * 100.0
             ),
-        );
-        println!(
+        ));
+        lines.push(format!(
             "\x1B[2K Clause Kind|Remv:{}, LBD2:{}, Binc:{}, Perm:{} ",
             im!("{:>9}", self.record, LogUsizeId::Removable, cdb.num_learnt),
             im!(
@@ -583,8 +726,8 @@ impl StateIF for State {
                 LogUsizeId::Permanent,
                 cdb.num_active - cdb.num_learnt
             ),
-        );
-        println!(
+        ));
+        lines.push(format!(
             "\x1B[2K     Restart|#BLK:{}, #RST:{}, eASG:{}, eLBD:{} ",
             im!(
                 "{:>9}",
@@ -610,8 +753,8 @@ impl StateIF for State {
                 LogF64Id::EmaLBD,
                 self.ema_lbd.get() / ave
             ),
-        );
-        println!(
+        ));
+        lines.push(format!(
             "\x1B[2K    Conflict|aLBD:{}, bjmp:{}, cnfl:{} |#stg:{} ",
             fm!("{:>9.2}", self.record, LogF64Id::AveLBD, self.ema_lbd.get()),
             fm!("{:>9.2}", self.record, LogF64Id::BLevel, self.b_lvl.get()),
@@ -622,8 +765,8 @@ impl StateIF for State {
                 LogUsizeId::Stagnation,
                 self.stats[Stat::Stagnation]
             ),
-        );
-        println!(
+        ));
+        lines.push(format!(
             "\x1B[2K   Clause DB|#rdc:{}, #sce:{} |blkR:{}, frcK:{} ",
             im!(
                 "{:>9}",
@@ -649,13 +792,24 @@ impl StateIF for State {
                 LogF64Id::RestartThrK,
                 self.restart_thr
             ),
-        );
+        ));
         if let Some(m) = mes {
-            println!("\x1B[2K    Strategy|mode: {}", m);
+            lines.push(format!("\x1B[2K    Strategy|mode: {}", m));
         } else {
-            println!("\x1B[2K    Strategy|mode: {:#}", self.strategy);
+            lines.push(format!("\x1B[2K    Strategy|mode: {:#}", self.strategy));
+        }
+        for o in &mut self.observers {
+            o.on_progress(&self.record);
+        }
+        if self.use_progress && self.progress_log {
+            self.dump(cdb, vars);
+        } else if self.use_progress {
+            print!("\x1B[8A\x1B[1G");
+            for l in &lines {
+                println!("{}", l);
+            }
+            self.flush("\x1B[2K");
         }
-        self.flush("\x1B[2K");
     }
 }
 
@@ -680,21 +834,16 @@ impl fmt::Display for State {
             self.target.num_of_variables, self.target.num_of_clauses,
         );
         let vclen = vc.len();
-        let fnlen = self.target.pathname.len();
+        let fname = self.target.pathname.as_ref().map_or("--", |p| p);
+        let fnlen = fname.len();
         let width = 59;
         if width < vclen + fnlen + 1 {
-            write!(
-                f,
-                "{:<w$} |time:{:>9.2}",
-                self.target.pathname,
-                tm,
-                w = width
-            )
+            write!(f, "{:<w$} |time:{:>9.2}", fname, tm, w = width)
         } else {
             write!(
                 f,
                 "{}{:>w$} |time:{:>9.2}",
-                self.target.pathname,
+                fname,
                 &vc,
                 tm,
                 w = width - fnlen,
@@ -726,6 +875,28 @@ pub enum LogUsizeId {
     End,
 }
 
+impl LogUsizeId {
+    /// names used in exported statistics
+    pub const NAMES: [&'static str; LogUsizeId::End as usize] = [
+        "propagate",
+        "decision",
+        "conflict",
+        "remain",
+        "fixed",
+        "elim",
+        "removable",
+        "lbd2",
+        "binclause",
+        "permanent",
+        "restart_block",
+        "restart_count",
+        "reduction",
+        "simplification",
+        "elimination",
+        "stagnation",
+    ];
+}
+
 /// Index for `f64` data, used in `ProgressRecord`
 pub enum LogF64Id {
     Progress = 0, //  0: progress: f64,
@@ -739,8 +910,22 @@ pub enum LogF64Id {
     End,
 }
 
+impl LogF64Id {
+    /// names used in exported statistics
+    pub const NAMES: [&'static str; LogF64Id::End as usize] = [
+        "progress",
+        "ema_asg",
+        "ema_lbd",
+        "ave_lbd",
+        "backjump_level",
+        "conflict_level",
+        "restart_k",
+        "restart_r",
+    ];
+}
+
 /// Record of old stats.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ProgressRecord {
     pub vali: [usize; LogUsizeId::End as usize],
     pub valf: [f64; LogF64Id::End as usize],
@@ -776,8 +961,7 @@ impl State {
              c ========================================================================================================="
         );
     }
-    fn dump(&mut self, cdb: &ClauseDB, vars: &[Var]) {
-        self.progress_cnt += 1;
+    fn dump(&self, cdb: &ClauseDB, vars: &[Var]) {
         let nv = vars.len() - 1;
         let fixed = self.num_solved_vars;
         let sum = fixed + self.num_eliminated_vars;
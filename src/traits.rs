@@ -1,11 +1,17 @@
-use crate::clause::{Clause, ClauseDB};
+use crate::clause::{CertifiedRecord, Clause, ClauseDB};
 use crate::config::Config;
+use crate::counter::ModelCount;
 use crate::eliminator::Eliminator;
 use crate::propagator::AssignStack;
-use crate::solver::{Solver, SolverResult};
-use crate::state::State;
-use crate::types::{CNFDescription, ClauseId, Flag, Lbool, Lit, MaybeInconsistent, VarId};
+use crate::solver::{Models, Solver, SolverException, SolverResult};
+use crate::state::{InterruptHandle, ProgressRecord, SearchStrategy, State};
+use crate::types::{
+    CNFDescription, ClauseId, Flag, Lbool, Lit, MaybeInconsistent, ParseError, ProofError, ProofId,
+    VarId,
+};
 use crate::var::Var;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
 
 /// API for Clause, providing `kill`.
 pub trait ClauseIF {
@@ -16,7 +22,10 @@ pub trait ClauseIF {
 /// API for clause management like `reduce`, `simplify`, `new_clause`, and so on.
 pub trait ClauseDBIF {
     fn new(nv: usize, nc: usize, certify: bool) -> Self;
+    /// add watcher lists for a new var.
+    fn new_var(&mut self);
     /// make a new clause from `state.new_learnt` and register it to clause database.
+    /// `proof_hints` are used as its antecedents in certification.
     fn attach(&mut self, state: &mut State, vars: &mut [Var], lbd: usize) -> ClauseId;
     /// unregister a clause `cid` from clause database and make the clause dead.
     fn detach(&mut self, cid: ClauseId);
@@ -52,14 +61,23 @@ pub trait ClauseDBIF {
     fn count(&self, alive: bool) -> usize;
     /// return the number of clauses which satisfy given flags and aren't DEAD.
     fn countf(&self, mask: Flag) -> usize;
-    /// record a clause to unsat certification
-    fn certificate_add(&mut self, vec: &[Lit]);
+    /// record a clause given as a part of the problem to unsat certification and return its id.
+    fn certificate_original(&mut self, vec: &[Lit]) -> ProofId;
+    /// record a clause derived from the clauses of `hints` to unsat certification and return its id.
+    fn certificate_add(&mut self, vec: &[Lit], hints: &[ProofId]) -> ProofId;
     /// record a deleted clause to unsat certification
-    fn certificate_delete(&mut self, vec: &[Lit]);
+    fn certificate_delete(&mut self, id: ProofId, vec: &[Lit]);
+    /// record the vars fixed by propagation at decision level zero as unit clauses
+    /// before their reasons are removed.
+    fn certificate_units(&mut self, asgs: &AssignStack, vars: &[Var]);
+    /// return `true` if certification needs antecedents of clauses.
+    fn use_hints(&self) -> bool;
     /// delete satisfied clauses at decision level zero.
     fn eliminate_satisfied_clauses(&mut self, elim: &mut Eliminator, vars: &mut [Var], occur: bool);
     /// emit an error if the db size (the number of clauses) is over the limit.
     fn check_size(&self, state: &State) -> MaybeInconsistent;
+    /// return the number of bytes used by clauses and watcher lists.
+    fn memory_usage(&self) -> usize;
 }
 
 /// API for Clause Id like `to_lit`, `is_lifted_lit` and so on.
@@ -83,6 +101,8 @@ pub trait Delete<T> {
 /// API for Eliminator like `activate`, `stop`, `eliminate` and so on.
 pub trait EliminatorIF {
     fn new(nv: usize) -> Eliminator;
+    /// expand the var queue for a new var.
+    fn new_var(&mut self);
     /// set eliminater's mode to **ready**.
     fn activate(&mut self);
     /// set eliminater's mode to **dormant**.
@@ -111,6 +131,14 @@ pub trait EliminatorIF {
     ) -> MaybeInconsistent;
     /// add assignments for eliminated vars to `model`.
     fn extend_model(&mut self, model: &mut Vec<i32>);
+    /// return the clauses removed by the elimination of a var, removing them from the
+    /// reconstruction stack.
+    fn take_eliminated_clauses(&mut self, vi: VarId) -> Vec<Vec<Lit>>;
+    /// return the reconstruction stack as clauses from the bottom. The first literal of each
+    /// clause is on the eliminated var; a unit clause holds the default value of it.
+    fn reconstruction_stack(&self) -> Vec<Vec<i32>>;
+    /// push clauses returned by `reconstruction_stack` to the reconstruction stack.
+    fn extend_reconstruction_stack(&mut self, stack: &[Vec<i32>]);
     /// register a clause id to all corresponding occur lists.
     fn add_cid_occur(&mut self, vars: &mut [Var], cid: ClauseId, c: &mut Clause, enqueue: bool);
     /// remove a clause id from literal's occur list.
@@ -119,6 +147,74 @@ pub trait EliminatorIF {
     fn remove_cid_occur(&mut self, vars: &mut [Var], cid: ClauseId, c: &mut Clause);
 }
 
+/// API for observing solver events like `on_learnt`, `on_restart` and so on.
+/// All methods do nothing by default. Use `InterruptHandle` to stop the solver from an observer.
+pub trait ObserverIF {
+    /// called when a clause is learnt.
+    fn on_learnt(&mut self, _lits: &[Lit], _lbd: usize) {}
+    /// called after a restart.
+    fn on_restart(&mut self) {}
+    /// called after a reduction of learnt clauses.
+    fn on_reduce(&mut self) {}
+    /// called after a simplification at decision level zero, and periodically during
+    /// variable elimination.
+    fn on_simplify(&mut self) {}
+    /// called when the search strategy is determined.
+    fn on_strategy_change(&mut self, _strategy: &SearchStrategy) {}
+    /// called when the solver updates its progress record.
+    fn on_progress(&mut self, _record: &ProgressRecord) {}
+}
+
+/// API for an external propagator which plugs theory reasoning into the search, like IPASIR-UP.
+/// It's notified of the assignments on vars registered by `SatSolverIF::add_observed_var`.
+pub trait ExternalPropagatorIF {
+    /// called with new assignments on observed vars at decision level `level`.
+    /// An assignment may be notified again after `notify_backtrack`.
+    fn notify_assignment(&mut self, _lits: &[Lit], _level: usize) {}
+    /// called after backtracking to decision level `level`; the assignments above it are undone.
+    fn notify_backtrack(&mut self, _level: usize) {}
+    /// called before each decision; return a literal to decide instead of the solver's choice.
+    fn decide(&mut self) -> Option<Lit> {
+        None
+    }
+    /// return literals implied by the theory under the notified assignments.
+    /// The reason of each of them is asked by `reason` only when conflict analysis needs it.
+    fn propagate(&mut self) -> Vec<Lit> {
+        Vec::new()
+    }
+    /// return the reason clause of `lit` returned by `propagate`, which contains `lit` and
+    /// the negations of some notified assignments.
+    fn reason(&mut self, lit: Lit) -> Vec<Lit>;
+    /// return a clause to add, like a conflict clause or a lemma. It's called until it returns `None`.
+    fn add_clause(&mut self) -> Option<Vec<Lit>> {
+        None
+    }
+    /// check a model, in which eliminated vars are 0. Return `false` to reject it with the
+    /// clauses given by `add_clause`; the model is accepted if no clause is given.
+    fn check_model(&mut self, _model: &[i32]) -> bool {
+        true
+    }
+}
+
+/// API for certification output like `record`, `finalize` and `flush`.
+pub trait ProofSinkIF {
+    /// write a clause given, added or deleted. `hints` are the antecedents of an added clause,
+    /// which may be empty even in the case that `use_hints` returns `true`.
+    fn record(&mut self, kind: CertifiedRecord, id: ProofId, lits: &[Lit], hints: &[ProofId]);
+    /// write the records closing a proof after the empty clause.
+    fn finalize(&mut self) {}
+    /// flush buffered records; returns the first error occurred while writing.
+    fn flush(&mut self) -> io::Result<()>;
+    /// return the records if they are kept in memory.
+    fn records(&self) -> Option<&[(CertifiedRecord, Vec<i32>)]> {
+        None
+    }
+    /// return `true` if the sink uses antecedents of added clauses.
+    fn use_hints(&self) -> bool {
+        false
+    }
+}
+
 /// API for Exponential Moving Average, EMA, like `get`, `reset`, `update` and so on.
 pub trait EmaIF {
     fn new(f: usize) -> Self;
@@ -158,6 +254,8 @@ pub trait LitIF {
 /// API for assignment like `propagate`, `enqueue`, `cancel_until`, and so on.
 pub trait PropagatorIF {
     fn new(n: usize) -> Self;
+    /// add a new unassigned var and put it into the heap on var order.
+    fn new_var(&mut self, vars: &[Var]);
     /// return the number of assignments.
     fn len(&self) -> usize;
     /// return `true` if there's no assignment.
@@ -176,6 +274,8 @@ pub trait PropagatorIF {
     fn propagate(&mut self, cdb: &mut ClauseDB, state: &mut State, vars: &mut [Var]) -> ClauseId;
     /// execute *backjump*.
     fn cancel_until(&mut self, vars: &mut [Var], lv: usize);
+    /// remove the assignment of a var at level zero, on which no other assignment depends.
+    fn unassign(&mut self, vars: &mut [Var], vi: VarId);
     /// add an assignment caused by a clause; emit an exception if solver becomes inconsistent.
     ///
     /// # Errors
@@ -186,14 +286,29 @@ pub trait PropagatorIF {
     fn enqueue_null(&mut self, v: &mut Var, sig: Lbool);
     /// unsafe enqueue; doesn't emit an exception.
     fn uncheck_enqueue(&mut self, vars: &mut [Var], l: Lit, cid: ClauseId);
+    /// add an assumption as a new decision level; emit an exception if it has been falsified.
+    ///
+    /// # Errors
+    ///
+    /// if the literal is already assigned to the opposite value.
+    fn assume(&mut self, vars: &mut [Var], l: Lit) -> MaybeInconsistent;
     /// unsafe assume; doesn't emit an exception.
     fn uncheck_assume(&mut self, vars: &mut [Var], l: Lit);
     /// update the internal heap on var order.
     fn update_order(&mut self, vec: &[Var], v: VarId);
+    /// put a var back into the internal heap on var order.
+    fn insert_order(&mut self, vec: &[Var], v: VarId);
     /// select a new decision variable.
     fn select_var(&mut self, vars: &[Var]) -> VarId;
-    /// dump all active clauses and fixed assignments in solver to a CNF file `fname`.
-    fn dump_cnf(&mut self, cdb: &ClauseDB, state: &State, vars: &[Var], fname: &str);
+    /// dump the active clauses except learnts and the assignments at level zero in solver
+    /// to a CNF file `fname`. The clauses are simplified by the assignments, which are written
+    /// as unit clauses; an inconsistent solver is dumped as the empty clause.
+    ///
+    /// # Errors
+    ///
+    /// IO error by failing to write to `fname`.
+    fn dump_cnf(&self, cdb: &ClauseDB, state: &State, vars: &[Var], fname: &Path)
+        -> io::Result<()>;
 }
 
 /// API for restart like `block_restart`, `force_restart` and so on.
@@ -215,18 +330,69 @@ pub trait SatSolverIF {
     /// make a solver for debug. Probably you should use `build` instead of this.
     fn new(config: &Config, cnf: &CNFDescription) -> Solver;
     /// make a solver and load a CNF into it.
+    /// With `use_certification`, the DRAT certification is written to `proof_filename` during search.
+    ///
+    /// # Errors
+    ///
+    /// `ParseError` by failing to load a CNF file or to create the proof file.
+    fn build(config: &Config) -> Result<Solver, ParseError>;
+    /// make a solver and load a CNF in DIMACS format from `reader`.
+    /// Mismatches between the header and clauses are errors unless `config.use_lenient_parser` is set.
     ///
     /// # Errors
     ///
-    /// IO error by failing to load a CNF file.
-    fn build(config: &Config) -> std::io::Result<Solver>;
+    /// `ParseError` by failing to read from `reader`.
+    fn from_reader<R: BufRead>(config: &Config, reader: R) -> Result<Solver, ParseError>;
+    /// add a new var to the solver and return its id.
+    fn new_var(&mut self) -> VarId;
     /// search an assignment.
     ///
     /// # Errors
     ///
     /// if solver becomes inconsistent by an internal error.
     fn solve(&mut self) -> SolverResult;
+    /// return a handle to stop `solve` from another thread.
+    fn interrupt_handle(&self) -> InterruptHandle;
+    /// register an observer of solver events.
+    fn add_observer(&mut self, observer: Box<dyn ObserverIF>);
+    /// plug an external propagator into the search, replacing the former one.
+    fn connect_propagator(&mut self, propagator: Box<dyn ExternalPropagatorIF>);
+    /// unplug the external propagator, and stop observing vars.
+    fn disconnect_propagator(&mut self) -> Option<Box<dyn ExternalPropagatorIF>>;
+    /// make the external propagator observe a var, which is frozen until disconnecting.
+    fn add_observed_var(&mut self, vi: VarId);
+    /// protect a var from elimination until it's melted as many times as frozen.
+    /// An eliminated var is brought back.
+    fn freeze(&mut self, vi: VarId);
+    /// undo a `freeze`. The var may be eliminated after the last one is undone.
+    fn melt(&mut self, vi: VarId);
+    /// return an iterator over the models which differ on `projection`, or on all vars if it's empty.
+    /// Each model is blocked by a clause on `projection` and an activation var assumed during the iteration.
+    /// The models are given back after dropping the iterator. The activation var is hidden from models.
+    ///
+    /// # Errors
+    ///
+    /// `SolverException::InvalidLiteral` if `projection` contains a var out of range.
+    fn models(&mut self, projection: &[VarId]) -> Result<Models<'_>, SolverException>;
+    /// search an assignment under assumptions, each of which is handled as a decision above level zero.
+    /// Learnt clauses are kept after returning, so it can be called repeatedly.
+    /// If it fails due to some of the assumptions, they are returned as `Certificate::UNSATUnderAssumptions`.
+    ///
+    /// # Errors
+    ///
+    /// if solver becomes inconsistent by an internal error.
+    fn solve_with_assumptions(&mut self, assumptions: &[Lit]) -> SolverResult;
+    /// run only the preprocessing phase of `solve`, which `solve` skips afterward.
+    /// Return `false` if the problem is found unsatisfiable.
+    /// The eliminated vars are recorded in the reconstruction stack of `elim`.
+    ///
+    /// # Errors
+    ///
+    /// `SolverException::OutOfMemory` by running out of the memory budget.
+    fn preprocess(&mut self) -> Result<bool, SolverException>;
     /// add a vector of `Lit` as a clause to the solver.
+    /// It can be called after `solve` returns; eliminated vars in it are brought back.
+    /// Return `None` if it makes the solver inconsistent, or it has been refuted.
     fn add_unchecked_clause(&mut self, v: &mut Vec<Lit>) -> Option<ClauseId>;
 }
 
@@ -234,8 +400,16 @@ pub trait SatSolverIF {
 pub trait StateIF {
     /// return an initialized state based on solver configuration and data about a CNF file.
     fn new(config: &Config, cnf: CNFDescription) -> State;
+    /// expand working spaces for a new var.
+    fn new_var(&mut self);
     /// return `true` if it is timed out.
     fn is_timeout(&self) -> bool;
+    /// return `true` if it is interrupted through `InterruptHandle`.
+    fn is_interrupted(&self) -> bool;
+    /// start budget accounting for a new `solve` call.
+    fn reset_budget(&mut self);
+    /// return `true` and set `exhausted` if the search runs out of a budget except memory.
+    fn is_over_budget(&mut self) -> bool;
     /// change heuristics based on stat data.
     fn adapt_strategy(&mut self, cdb: &mut ClauseDB);
     /// write a header of stat data to stdio.
@@ -245,6 +419,19 @@ pub trait StateIF {
     fn flush(&self, mes: &str);
 }
 
+/// API for model counting, providing `count_models`.
+pub trait CounterIF {
+    /// count the models on the vars in `state.projection`, or on all vars if it's empty,
+    /// by DPLL with connected components and a cache of their counts. Clauses are kept.
+    /// It gives up with `ModelCount::Unknown` by running out of a budget; the memory budget
+    /// includes the cache.
+    ///
+    /// # Errors
+    ///
+    /// if it's interrupted by `InterruptHandle::interrupt`.
+    fn count_models(&mut self) -> Result<ModelCount, SolverException>;
+}
+
 /// API for SAT validator like `inject_assignment`, `validate` and so on.
 pub trait ValidatorIF {
     /// load a assignment set into solver.
@@ -255,6 +442,13 @@ pub trait ValidatorIF {
     fn inject_assigmnent(&mut self, vec: &[i32]) -> MaybeInconsistent;
     /// return `true` is the loaded assignment set is satisfiable (a model of a problem).
     fn validate(&self) -> Option<Vec<i32>>;
+    /// check a DRAT proof in text or binary of the loaded problem by reverse unit propagation,
+    /// and return the number of verified lemmas. RAT lemmas aren't supported.
+    ///
+    /// # Errors
+    ///
+    /// the first failing record, or `ProofError::NoRefutation` if the proof is incomplete.
+    fn check_proof<R: Read>(&mut self, proof: R) -> Result<usize, ProofError>;
 }
 
 /// API for Var, providing `new` and `new_vars`.
@@ -275,6 +469,8 @@ pub trait VarDBIF {
     fn compute_lbd(&self, vec: &[Lit], keys: &mut [usize]) -> usize;
     /// update the variable's activity.
     fn bump_activity(&mut self, inc: &mut f64, vi: VarId);
+    /// return the number of bytes used by vars and their occurrence lists.
+    fn memory_usage(&self) -> usize;
 }
 
 /// API for 'watcher list' like `attach`, `detach`, `detach_with` and so on.
@@ -1,6 +1,7 @@
 //! Basic types
 use crate::traits::{Delete, LitIF};
 use std::fmt;
+use std::io::BufRead;
 use std::ops::Neg;
 
 /// 'Variable' identifier or 'variable' index, starting with one.
@@ -13,6 +14,10 @@ pub type ClauseId = u32;
 /// a dummy clause index.
 pub const NULL_CLAUSE: ClauseId = 0;
 
+/// Clause identifier in certifications, starting with one.
+/// Unlike `ClauseId`, ids are never re-used.
+pub type ProofId = u64;
+
 /// Literal encoded on `u32` as:
 ///
 /// - the literal corresponding to a positive occurrence of *variable `n` is `2 * n` and
@@ -114,20 +119,123 @@ pub enum SolverError {
 /// A Return type used by solver functions
 pub type MaybeInconsistent = Result<(), SolverError>;
 
+/// Errors in loading a CNF in DIMACS format.
+/// Lines and columns are counted from 1.
+#[derive(Debug)]
+pub enum ParseError {
+    /// failed to read the input
+    IOError(std::io::Error),
+    /// no `p cnf` line before clauses
+    MissingHeader,
+    /// a `p cnf` line without two numbers
+    BadHeader { line: usize },
+    /// a token which isn't an integer
+    BadLiteral { line: usize, column: usize },
+    /// a literal on a var beyond the number declared in the header
+    OutOfRangeLiteral {
+        line: usize,
+        column: usize,
+        literal: i32,
+    },
+    /// the number of clauses differs from the header
+    ClauseCountMismatch { expected: usize, found: usize },
+    /// a status line other than `s SATISFIABLE` in a model
+    NoModel { line: usize, status: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::IOError(e) => write!(f, "{}", e),
+            ParseError::MissingHeader => write!(f, "no 'p cnf' line"),
+            ParseError::BadHeader { line } => write!(f, "line {}: bad 'p cnf' line", line),
+            ParseError::BadLiteral { line, column } => {
+                write!(f, "line {}, column {}: bad literal", line, column)
+            }
+            ParseError::OutOfRangeLiteral {
+                line,
+                column,
+                literal,
+            } => write!(
+                f,
+                "line {}, column {}: literal {} is out of range",
+                line, column, literal
+            ),
+            ParseError::ClauseCountMismatch { expected, found } => write!(
+                f,
+                "found {} clauses while the header declares {}",
+                found, expected
+            ),
+            ParseError::NoModel { line, status } => {
+                write!(f, "line {}: no model for '{}'", line, status)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> ParseError {
+        ParseError::IOError(e)
+    }
+}
+
+/// Failures found in checking a DRAT proof by `ValidatorIF::check_proof`.
+/// Steps are the positions of records counted from 1, which are line numbers in a text proof.
+#[derive(Debug)]
+pub enum ProofError {
+    /// failed to read the proof
+    IOError(std::io::Error),
+    /// a record which isn't a clause
+    BadRecord { step: usize },
+    /// a lemma which isn't derived by reverse unit propagation
+    NotRUP { step: usize, lemma: Vec<i32> },
+    /// the proof ends without deriving the empty clause
+    NoRefutation,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofError::IOError(e) => write!(f, "{}", e),
+            ProofError::BadRecord { step } => write!(f, "step {}: bad record", step),
+            ProofError::NotRUP { step, lemma } => {
+                write!(f, "step {}: lemma {:?} isn't RUP", step, lemma)
+            }
+            ProofError::NoRefutation => write!(f, "no empty clause is derived"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+impl From<std::io::Error> for ProofError {
+    fn from(e: std::io::Error) -> ProofError {
+        ProofError::IOError(e)
+    }
+}
+
 /// data about a problem.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct CNFDescription {
     pub num_of_variables: usize,
     pub num_of_clauses: usize,
-    pub pathname: String,
+    /// the source file if it was loaded from a file
+    pub pathname: Option<String>,
 }
 
-impl Default for CNFDescription {
-    fn default() -> CNFDescription {
+impl<V: AsRef<[i32]>> From<&[V]> for CNFDescription {
+    fn from(vec: &[V]) -> CNFDescription {
+        let num_of_variables = vec
+            .iter()
+            .map(|c| c.as_ref().iter().map(|l| l.abs()).max().unwrap_or(0))
+            .max()
+            .unwrap_or(0) as usize;
         CNFDescription {
-            num_of_variables: 0,
-            num_of_clauses: 0,
-            pathname: "".to_string(),
+            num_of_variables,
+            num_of_clauses: vec.len(),
+            pathname: None,
         }
     }
 }
@@ -139,8 +247,59 @@ impl fmt::Display for CNFDescription {
             num_of_clauses: nc,
             pathname: path,
         } = &self;
-        write!(f, "CNF({}, {}, {})", nv, nc, path)
+        write!(
+            f,
+            "CNF({}, {}, {})",
+            nv,
+            nc,
+            path.as_ref().map_or("--", |p| p)
+        )
+    }
+}
+
+/// reads a CNF or a model in `v` lines, and returns the number of vars in the `p` line, and the
+/// groups of integers terminated by `0`. A status line should be `s SATISFIABLE`.
+pub fn read_dimacs_integers<R: BufRead>(
+    reader: R,
+) -> Result<(Option<usize>, Vec<Vec<i32>>), ParseError> {
+    let mut nv = None;
+    let mut vec = Vec::new();
+    let mut group = Vec::new();
+    for (i, buf) in reader.lines().enumerate() {
+        let buf = buf?;
+        let line = i + 1;
+        let mut tokens = buf.split_whitespace().peekable();
+        match tokens.peek() {
+            None => continue,
+            Some(t) if t.starts_with('c') => continue,
+            Some(t) if t.starts_with('s') => {
+                if tokens.nth(1) != Some("SATISFIABLE") {
+                    return Err(ParseError::NoModel {
+                        line,
+                        status: buf.trim().to_string(),
+                    });
+                }
+                continue;
+            }
+            Some(t) if t.starts_with('p') => {
+                nv = tokens.nth(2).and_then(|n| n.parse::<usize>().ok());
+                continue;
+            }
+            _ => (),
+        }
+        for token in tokens.filter(|t| *t != "v") {
+            let column = token.as_ptr() as usize - buf.as_ptr() as usize + 1;
+            match token.parse::<i32>() {
+                Ok(0) => vec.push(std::mem::take(&mut group)),
+                Ok(l) => group.push(l),
+                Err(_) => return Err(ParseError::BadLiteral { line, column }),
+            }
+        }
+    }
+    if !group.is_empty() {
+        vec.push(group);
     }
+    Ok((nv, vec))
 }
 
 /// convert `[Lit]` to `[i32]` (for debug)
@@ -187,5 +346,9 @@ bitflags! {
         const ELIMINATED   = 0b0000_0000_0010_0000;
         /// mark to run garbage collector on the corresponding watcher lists
         const TOUCHED      = 0b0000_0000_0100_0000;
+        /// a var is observed by an external propagator.
+        const OBSERVED     = 0b0000_0000_1000_0000;
+        /// a var is frozen by users, and isn't eliminated.
+        const FROZEN       = 0b0000_0001_0000_0000;
     }
 }
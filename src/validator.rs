@@ -1,6 +1,13 @@
 use crate::solver::Solver;
-use crate::traits::{LitIF, PropagatorIF, ValidatorIF, VarDBIF};
-use crate::types::{Lit, MaybeInconsistent, SolverError, NULL_CLAUSE};
+use crate::traits::{ClauseDBIF, FlagIF, LitIF, PropagatorIF, ValidatorIF, VarDBIF};
+use crate::types::{
+    ClauseId, Flag, Lit, MaybeInconsistent, ProofError, SolverError, FALSE, NULL_CLAUSE, TRUE,
+};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// a clause addition or deletion in a DRAT proof, with its step.
+type DratRecord = (usize, bool, Vec<i32>);
 
 impl ValidatorIF for Solver {
     fn inject_assigmnent(&mut self, vec: &[i32]) -> MaybeInconsistent {
@@ -29,4 +36,176 @@ impl ValidatorIF for Solver {
         }
         None
     }
+    fn check_proof<R: Read>(&mut self, mut proof: R) -> Result<usize, ProofError> {
+        let mut bytes = Vec::new();
+        proof.read_to_end(&mut bytes)?;
+        // a text proof has no zero byte while every binary record ends with it.
+        let records = if bytes.contains(&0) {
+            parse_binary_drat(&bytes)?
+        } else {
+            parse_text_drat(&bytes)?
+        };
+        debug_assert!(self.asgs.is_zero());
+        if !self.state.ok
+            || self
+                .asgs
+                .propagate(&mut self.cdb, &mut self.state, &mut self.vars)
+                != NULL_CLAUSE
+        {
+            return Ok(0);
+        }
+        // clauses are identified by their sorted literals.
+        let mut index: HashMap<Vec<Lit>, Vec<ClauseId>> = HashMap::new();
+        for (cid, c) in self.cdb.clause.iter().enumerate().skip(1) {
+            if !c.is(Flag::DEAD) {
+                let mut key = c.lits.clone();
+                key.sort_unstable();
+                index.entry(key).or_default().push(cid as ClauseId);
+            }
+        }
+        let mut num_lemmas = 0;
+        let mut garbage = false;
+        for (step, deletion, lemma) in records {
+            let max = lemma.iter().map(|l| l.unsigned_abs()).max().unwrap_or(0) as usize;
+            self.reserve_var(max);
+            let mut lits = lemma
+                .iter()
+                .map(|i| Lit::from_int(*i))
+                .collect::<Vec<Lit>>();
+            lits.sort_unstable();
+            lits.dedup();
+            if deletion {
+                // Like drat-trim, ignore deletions of reasons at level zero and unknown clauses.
+                if let Some(ids) = index.get_mut(&lits) {
+                    if let Some(n) = ids.iter().position(|cid| !locked(self, *cid)) {
+                        self.cdb.detach(ids.swap_remove(n));
+                        garbage = true;
+                    }
+                }
+                continue;
+            }
+            if garbage {
+                self.cdb.garbage_collect();
+                garbage = false;
+            }
+            if !rup(self, &lits) {
+                return Err(ProofError::NotRUP { step, lemma });
+            }
+            num_lemmas += 1;
+            if lits.iter().any(|l| self.vars.assigned(*l) == TRUE) {
+                continue;
+            }
+            let key = lits.clone();
+            lits.retain(|l| self.vars.assigned(*l) != FALSE);
+            match lits.len() {
+                0 => return Ok(num_lemmas),
+                1 => {
+                    self.asgs
+                        .enqueue_null(&mut self.vars[lits[0].vi()], lits[0].lbool());
+                    if self
+                        .asgs
+                        .propagate(&mut self.cdb, &mut self.state, &mut self.vars)
+                        != NULL_CLAUSE
+                    {
+                        return Ok(num_lemmas);
+                    }
+                }
+                _ => {
+                    let cid = self.cdb.new_clause(&lits, 0, false);
+                    index.entry(key).or_default().push(cid);
+                }
+            }
+        }
+        Err(ProofError::NoRefutation)
+    }
+}
+
+/// returns `true` if clause `cid` is the reason of an assignment at decision level zero.
+fn locked(s: &Solver, cid: ClauseId) -> bool {
+    s.cdb.clause[cid as usize]
+        .lits
+        .iter()
+        .any(|l| s.vars.assigned(*l) == TRUE && s.vars[l.vi()].reason == cid)
+}
+
+/// returns `true` if the negation of `lits` leads to a conflict by unit propagation.
+fn rup(s: &mut Solver, lits: &[Lit]) -> bool {
+    let Solver {
+        ref mut asgs,
+        ref mut cdb,
+        ref mut state,
+        ref mut vars,
+        ..
+    } = s;
+    let conflict = lits.iter().any(|l| asgs.assume(vars, l.negate()).is_err())
+        || asgs.propagate(cdb, state, vars) != NULL_CLAUSE;
+    asgs.cancel_until(vars, 0);
+    conflict
+}
+
+fn parse_text_drat(bytes: &[u8]) -> Result<Vec<DratRecord>, ProofError> {
+    let mut records = Vec::new();
+    for (i, line) in String::from_utf8_lossy(bytes).lines().enumerate() {
+        let step = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+        let deletion = line.starts_with('d');
+        let mut lemma = Vec::new();
+        let mut closed = false;
+        for token in line.trim_start_matches('d').split_whitespace() {
+            match token.parse::<i32>() {
+                Ok(0) if !closed => closed = true,
+                Ok(l) if !closed => lemma.push(l),
+                _ => return Err(ProofError::BadRecord { step }),
+            }
+        }
+        if !closed {
+            return Err(ProofError::BadRecord { step });
+        }
+        records.push((step, deletion, lemma));
+    }
+    Ok(records)
+}
+
+/// parses records of `a` or `d` followed by literals in a variable-length encoding
+/// of `2 * var + sign`, and a terminating zero.
+fn parse_binary_drat(bytes: &[u8]) -> Result<Vec<DratRecord>, ProofError> {
+    let mut records = Vec::new();
+    let mut iter = bytes.iter();
+    while let Some(head) = iter.next() {
+        let step = records.len() + 1;
+        let deletion = match head {
+            b'a' => false,
+            b'd' => true,
+            _ => return Err(ProofError::BadRecord { step }),
+        };
+        let mut lemma = Vec::new();
+        loop {
+            let mut u: u32 = 0;
+            let mut shift = 0;
+            loop {
+                let b = match iter.next() {
+                    Some(b) if shift < 32 => *b,
+                    _ => return Err(ProofError::BadRecord { step }),
+                };
+                u |= u32::from(b & 0x7F) << shift;
+                shift += 7;
+                if b & 0x80 == 0 {
+                    break;
+                }
+            }
+            match u {
+                0 => break,
+                1 => return Err(ProofError::BadRecord { step }),
+                _ => {
+                    let v = (u >> 1) as i32;
+                    lemma.push(if u & 1 == 0 { v } else { -v });
+                }
+            }
+        }
+        records.push((step, deletion, lemma));
+    }
+    Ok(records)
 }
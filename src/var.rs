@@ -2,6 +2,7 @@ use crate::clause::Clause;
 use crate::traits::*;
 use crate::types::*;
 use std::fmt;
+use std::mem::size_of;
 
 const VAR_ACTIVITY_MAX: f64 = 1e240;
 const VAR_ACTIVITY_SCALE1: f64 = 1e-30;
@@ -25,6 +26,8 @@ pub struct Var {
     pub pos_occurs: Vec<ClauseId>,
     /// list of clauses which contain this variable negatively.
     pub neg_occurs: Vec<ClauseId>,
+    /// the number of `freeze` calls which aren't melted yet.
+    pub freezes: usize,
     flags: Flag,
 }
 
@@ -43,6 +46,7 @@ impl VarIF for Var {
             activity: 0.0,
             pos_occurs: Vec::new(),
             neg_occurs: Vec::new(),
+            freezes: 0,
             flags: Flag::empty(),
         }
     }
@@ -111,6 +115,14 @@ impl VarDBIF for [Var] {
             *inc *= VAR_ACTIVITY_SCALE2;
         }
     }
+    fn memory_usage(&self) -> usize {
+        self.iter()
+            .map(|v| {
+                size_of::<Var>()
+                    + (v.pos_occurs.capacity() + v.neg_occurs.capacity()) * size_of::<ClauseId>()
+            })
+            .sum()
+    }
 }
 
 impl fmt::Display for Var {
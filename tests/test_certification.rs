@@ -0,0 +1,532 @@
+use splr::clause::CertifiedRecord;
+use splr::config::Config;
+use splr::proof::{ProofFormat, ProofWriter};
+use splr::solver::{Certificate, Solver};
+use splr::traits::*;
+use splr::types::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::Write;
+
+/// A naive DRAT checker accepting RUP lemmas only.
+/// Like drat-trim, it ignores deletions of unit clauses under the top-level assignment.
+struct Checker {
+    clauses: Vec<Option<Vec<i32>>>,
+    index: HashMap<Vec<i32>, Vec<usize>>,
+}
+
+impl Checker {
+    fn new(cnf: &[Vec<i32>]) -> Checker {
+        let mut checker = Checker {
+            clauses: Vec::new(),
+            index: HashMap::new(),
+        };
+        for c in cnf {
+            checker.add(c);
+        }
+        checker
+    }
+    fn add(&mut self, c: &[i32]) {
+        let key = normalize(c);
+        self.index
+            .entry(key.clone())
+            .or_default()
+            .push(self.clauses.len());
+        self.clauses.push(Some(key));
+    }
+    fn delete(&mut self, c: &[i32]) -> Result<(), String> {
+        let key = normalize(c);
+        let i = match self.index.get_mut(&key).and_then(|v| v.pop()) {
+            Some(i) => i,
+            None => return Err(format!("deletion of a non-existent clause {:?}", c)),
+        };
+        if let Some(assign) = self.propagate(&[]) {
+            let unassigned = key
+                .iter()
+                .filter(|l| !assign.contains_key(&l.abs()))
+                .count();
+            let satisfied = key
+                .iter()
+                .filter(|l| assign.get(&l.abs()) == Some(&(0 < **l)))
+                .count();
+            if unassigned == 0 && satisfied == 1 {
+                self.index.get_mut(&key).unwrap().push(i);
+                return Ok(());
+            }
+        }
+        self.clauses[i] = None;
+        Ok(())
+    }
+    /// returns the top-level assignment under `assumptions` or `None` by a conflict.
+    fn propagate(&self, assumptions: &[i32]) -> Option<HashMap<i32, bool>> {
+        let mut assign: HashMap<i32, bool> = HashMap::new();
+        for l in assumptions {
+            match assign.get(&l.abs()) {
+                Some(b) if *b != (0 < *l) => return None,
+                _ => {
+                    assign.insert(l.abs(), 0 < *l);
+                }
+            }
+        }
+        loop {
+            let mut updated = false;
+            for c in self.clauses.iter().filter_map(|c| c.as_ref()) {
+                let mut unassigned = None;
+                let mut count = 0;
+                let mut satisfied = false;
+                for l in c {
+                    match assign.get(&l.abs()) {
+                        Some(b) if *b == (0 < *l) => {
+                            satisfied = true;
+                            break;
+                        }
+                        Some(_) => (),
+                        None => {
+                            unassigned = Some(*l);
+                            count += 1;
+                        }
+                    }
+                }
+                if satisfied {
+                    continue;
+                }
+                match (count, unassigned) {
+                    (0, _) => return None,
+                    (1, Some(l)) => {
+                        assign.insert(l.abs(), 0 < l);
+                        updated = true;
+                    }
+                    _ => (),
+                }
+            }
+            if !updated {
+                return Some(assign);
+            }
+        }
+    }
+    fn check(&mut self, proof: &[(CertifiedRecord, Vec<i32>)]) -> Result<usize, String> {
+        let mut deleted = 0;
+        for (r, c) in proof {
+            match r {
+                CertifiedRecord::SENTINEL | CertifiedRecord::ORIGINAL => (),
+                CertifiedRecord::ADD => {
+                    let negated = c.iter().map(|l| -l).collect::<Vec<i32>>();
+                    if self.propagate(&negated).is_some() {
+                        return Err(format!("a non-RUP lemma {:?}", c));
+                    }
+                    self.add(c);
+                }
+                CertifiedRecord::DELETE => {
+                    self.delete(c)?;
+                    deleted += 1;
+                }
+            }
+        }
+        if self.propagate(&[]).is_some() {
+            return Err("no refutation".to_string());
+        }
+        Ok(deleted)
+    }
+}
+
+fn normalize(c: &[i32]) -> Vec<i32> {
+    let mut v = c.to_vec();
+    v.sort();
+    v.dedup();
+    v
+}
+
+/// returns the number of deleted clauses in a verified proof.
+fn certify(cnf: Vec<Vec<i32>>) -> usize {
+    let config = Config {
+        use_certification: true,
+        ..Config::default()
+    };
+    let mut s = Solver::try_from((config, cnf.clone())).expect("failed to build");
+    match s.solve() {
+        Ok(Certificate::UNSAT) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    let mut checker = Checker::new(&cnf);
+    let proof = s.cdb.certified.as_ref().and_then(|p| p.records()).unwrap();
+    match checker.check(proof) {
+        Ok(n) => n,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+fn pigeon_hole(n: i32) -> Vec<Vec<i32>> {
+    let lit = |p: i32, h: i32| p * n + h + 1;
+    let mut clauses = Vec::new();
+    for p in 0..=n {
+        clauses.push((0..n).map(|h| lit(p, h)).collect::<Vec<i32>>());
+    }
+    for h in 0..n {
+        for p in 0..=n {
+            for q in p + 1..=n {
+                clauses.push(vec![-lit(p, h), -lit(q, h)]);
+            }
+        }
+    }
+    clauses
+}
+
+/// a random 3-SAT problem made by xorshift, which updates `seed`
+fn random_3sat(seed: &mut u64, nv: u64, nc: usize) -> Vec<Vec<i32>> {
+    let mut rand = |n: u64| {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed % n
+    };
+    (0..nc)
+        .map(|_| {
+            (0..3)
+                .map(|_| {
+                    let v = rand(nv) as i32 + 1;
+                    if rand(2) == 0 {
+                        v
+                    } else {
+                        -v
+                    }
+                })
+                .collect::<Vec<i32>>()
+        })
+        .collect::<Vec<Vec<i32>>>()
+}
+
+#[test]
+fn certify_pigeon_hole() {
+    assert!(0 < certify(pigeon_hole(5)));
+}
+
+#[test]
+fn certify_with_given_units() {
+    // units given before the other clauses simplify them while loading.
+    let mut cnf = vec![vec![31], vec![-32]];
+    for c in pigeon_hole(5) {
+        let mut c = c.clone();
+        c.push(-31);
+        c.push(32);
+        cnf.push(c);
+    }
+    cnf.push(vec![31, 1, 2]);
+    assert!(0 < certify(cnf));
+}
+
+#[test]
+fn certify_random_3sat() {
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut refuted = 0;
+    for _ in 0..8 {
+        let cnf = random_3sat(&mut seed, 50, 300);
+        let mut s = Solver::try_from((Config::default(), cnf.clone())).expect("failed to build");
+        if let Ok(Certificate::UNSAT) = s.solve() {
+            certify(cnf);
+            refuted += 1;
+        }
+    }
+    assert!(0 < refuted);
+}
+
+/// returns the contents of a proof file written by `Solver::build` in the way of `splr -c`.
+fn solve_to_file(cnf: &[Vec<i32>], format: ProofFormat) -> Vec<u8> {
+    let dir = std::env::temp_dir().join(format!(
+        "splr-certification-{}-{:?}-{}",
+        std::process::id(),
+        format,
+        cnf.len(),
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    let cnf_file = dir.join("problem.cnf");
+    let mut out = fs::File::create(&cnf_file).unwrap();
+    let nv = cnf.iter().flatten().map(|l| l.abs()).max().unwrap_or(0);
+    writeln!(out, "p cnf {} {}", nv, cnf.len()).unwrap();
+    for c in cnf {
+        for l in c {
+            write!(out, "{} ", l).unwrap();
+        }
+        writeln!(out, "0").unwrap();
+    }
+    let config = Config {
+        cnf_filename: cnf_file,
+        output_dirname: dir.clone(),
+        use_certification: true,
+        use_binary_proof: format == ProofFormat::Binary,
+        use_frat: format == ProofFormat::FRAT,
+        ..Config::default()
+    };
+    let mut s = Solver::build(&config).expect("failed to build");
+    match s.solve() {
+        Ok(Certificate::UNSAT) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert!(s.cdb.certified.as_ref().unwrap().records().is_none());
+    let p = s.cdb.certified.as_mut().unwrap();
+    p.finalize();
+    p.flush().unwrap();
+    let bytes = fs::read(dir.join(&config.proof_filename)).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    bytes
+}
+
+/// returns the records in a verified DRAT proof file.
+fn certify_to_file(binary: bool) -> Vec<(CertifiedRecord, Vec<i32>)> {
+    let cnf = pigeon_hole(5);
+    let format = if binary {
+        ProofFormat::Binary
+    } else {
+        ProofFormat::Text
+    };
+    let bytes = solve_to_file(&cnf, format);
+    let proof = if binary {
+        parse_binary_proof(&bytes)
+    } else {
+        parse_text_proof(&String::from_utf8(bytes).unwrap())
+    };
+    assert!(Checker::new(&cnf).check(&proof).is_ok());
+    proof
+}
+
+fn parse_text_proof(text: &str) -> Vec<(CertifiedRecord, Vec<i32>)> {
+    text.lines()
+        .map(|line| {
+            let mut lits = line
+                .split_whitespace()
+                .filter(|t| *t != "d")
+                .map(|t| t.parse::<i32>().unwrap())
+                .collect::<Vec<i32>>();
+            assert_eq!(lits.pop(), Some(0));
+            if line.starts_with('d') {
+                (CertifiedRecord::DELETE, lits)
+            } else {
+                (CertifiedRecord::ADD, lits)
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+fn parse_binary_proof(bytes: &[u8]) -> Vec<(CertifiedRecord, Vec<i32>)> {
+    let mut proof = Vec::new();
+    let mut iter = bytes.iter();
+    while let Some(head) = iter.next() {
+        let kind = match head {
+            b'a' => CertifiedRecord::ADD,
+            b'd' => CertifiedRecord::DELETE,
+            _ => panic!("invalid record head {}", head),
+        };
+        let mut lits = Vec::new();
+        loop {
+            let mut u: u32 = 0;
+            let mut shift = 0;
+            loop {
+                let b = *iter.next().unwrap();
+                u |= u32::from(b & 0x7F) << shift;
+                shift += 7;
+                if b & 0x80 == 0 {
+                    break;
+                }
+            }
+            if u == 0 {
+                break;
+            }
+            let v = (u >> 1) as i32;
+            lits.push(if u & 1 == 0 { v } else { -v });
+        }
+        proof.push((kind, lits));
+    }
+    proof
+}
+
+#[test]
+fn certify_text_file() {
+    let proof = certify_to_file(false);
+    assert!(proof.iter().any(|(r, _)| *r == CertifiedRecord::DELETE));
+}
+
+#[test]
+fn certify_binary_file() {
+    let proof = certify_to_file(true);
+    assert!(proof.iter().any(|(r, _)| *r == CertifiedRecord::DELETE));
+}
+
+#[test]
+fn binary_proof_encoding() {
+    let mut w = ProofWriter::new(Vec::new(), ProofFormat::Binary);
+    w.record(
+        CertifiedRecord::ADD,
+        1,
+        &[1, -2, 3]
+            .iter()
+            .map(|i| Lit::from_int(*i))
+            .collect::<Vec<Lit>>(),
+        &[],
+    );
+    w.record(CertifiedRecord::DELETE, 1, &[Lit::from_int(-100)], &[]);
+    w.flush().unwrap();
+    assert_eq!(
+        w.into_inner(),
+        vec![b'a', 0x02, 0x05, 0x06, 0x00, b'd', 0xC9, 0x01, 0x00]
+    );
+}
+
+/// checks a FRAT proof and returns the number of lemmas with antecedents.
+/// A lemma with antecedents is checked by unit propagation on them in the given order;
+/// one without antecedents should be RUP.
+fn check_frat(cnf: &[Vec<i32>], text: &str) -> Result<usize, String> {
+    let originals = cnf.iter().map(|c| normalize(c)).collect::<Vec<_>>();
+    let mut alive: HashMap<u64, Vec<i32>> = HashMap::new();
+    let mut used: Vec<u64> = Vec::new();
+    let mut refuted = false;
+    let mut finalized = false;
+    let mut hinted = 0;
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        let head = tokens.next().ok_or("an empty line")?;
+        let id = tokens
+            .next()
+            .and_then(|t| t.parse::<u64>().ok())
+            .ok_or(format!("no id in {}", line))?;
+        let mut lits = Vec::new();
+        for t in &mut tokens {
+            match t.parse::<i32>() {
+                Ok(0) => break,
+                Ok(l) => lits.push(l),
+                Err(_) => return Err(format!("invalid literal in {}", line)),
+            }
+        }
+        let lits = normalize(&lits);
+        let hints = match tokens.next() {
+            Some("l") => tokens
+                .map(|t| t.parse::<u64>().unwrap())
+                .take_while(|h| *h != 0)
+                .collect::<Vec<u64>>(),
+            Some(t) => return Err(format!("unexpected {} in {}", t, line)),
+            None => Vec::new(),
+        };
+        if finalized && head != "f" {
+            return Err(format!("{} after finalization", line));
+        }
+        match head {
+            "o" | "a" => {
+                if used.contains(&id) {
+                    return Err(format!("a reused id in {}", line));
+                }
+                used.push(id);
+                if head == "o" && !originals.contains(&lits) {
+                    return Err(format!("a non-original clause {}", line));
+                }
+                if head == "a" {
+                    if hints.is_empty() {
+                        let checker = Checker {
+                            clauses: alive.values().cloned().map(Some).collect(),
+                            index: HashMap::new(),
+                        };
+                        let negated = lits.iter().map(|l| -l).collect::<Vec<i32>>();
+                        if checker.propagate(&negated).is_some() {
+                            return Err(format!("a non-RUP lemma {}", line));
+                        }
+                    } else {
+                        check_hints(&alive, &lits, &hints)
+                            .map_err(|e| format!("{} in {}", e, line))?;
+                        hinted += 1;
+                    }
+                    refuted |= lits.is_empty();
+                }
+                alive.insert(id, lits);
+            }
+            "d" | "f" => {
+                if alive.remove(&id) != Some(lits) {
+                    return Err(format!("a mismatched clause in {}", line));
+                }
+                finalized |= head == "f";
+            }
+            _ => return Err(format!("an unknown step {}", line)),
+        }
+    }
+    if !refuted {
+        return Err("no empty clause".to_string());
+    }
+    if !alive.is_empty() {
+        return Err(format!("{} clauses aren't finalized", alive.len()));
+    }
+    Ok(hinted)
+}
+
+fn check_hints(alive: &HashMap<u64, Vec<i32>>, lemma: &[i32], hints: &[u64]) -> Result<(), String> {
+    let mut assign = lemma.iter().map(|l| -l).collect::<Vec<i32>>();
+    for h in hints {
+        let c = alive.get(h).ok_or(format!("an unknown hint {}", h))?;
+        if c.iter().any(|l| assign.contains(l)) {
+            return Err(format!("a satisfied hint {}", h));
+        }
+        let rest = c
+            .iter()
+            .filter(|l| !assign.contains(&-**l))
+            .collect::<Vec<_>>();
+        match rest.len() {
+            0 => return Ok(()),
+            1 => assign.push(*rest[0]),
+            _ => return Err(format!("a non-unit hint {}", h)),
+        }
+    }
+    Err("no conflict".to_string())
+}
+
+fn certify_frat(cnf: &[Vec<i32>]) -> usize {
+    let bytes = solve_to_file(cnf, ProofFormat::FRAT);
+    match check_frat(cnf, &String::from_utf8(bytes).unwrap()) {
+        Ok(n) => n,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn certify_frat_file() {
+    assert!(0 < certify_frat(&pigeon_hole(5)));
+    let mut cnf = vec![vec![31], vec![-32]];
+    for c in pigeon_hole(5) {
+        let mut c = c.clone();
+        c.push(-31);
+        c.push(32);
+        cnf.push(c);
+    }
+    cnf.push(vec![31, 1, 2]);
+    assert!(0 < certify_frat(&cnf));
+}
+
+#[test]
+fn certify_frat_random_3sat() {
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut refuted = 0;
+    for _ in 0..8 {
+        let cnf = random_3sat(&mut seed, 50, 300);
+        let mut s = Solver::try_from((Config::default(), cnf.clone())).expect("failed to build");
+        if let Ok(Certificate::UNSAT) = s.solve() {
+            certify_frat(&cnf);
+            refuted += 1;
+        }
+    }
+    assert!(0 < refuted);
+}
+
+#[test]
+fn check_proof_by_validator() {
+    let cnf = pigeon_hole(5);
+    for format in &[ProofFormat::Text, ProofFormat::Binary] {
+        let bytes = solve_to_file(&cnf, *format);
+        let mut s = Solver::try_from((Config::default(), cnf.clone())).expect("failed to build");
+        assert!(0 < s.check_proof(&bytes[..]).unwrap());
+    }
+    let mut s = Solver::try_from((Config::default(), cnf.clone())).expect("failed to build");
+    match s.check_proof("c a comment\n1 2 0\n0\n".as_bytes()) {
+        Err(ProofError::NotRUP { step: 2, lemma }) => assert_eq!(lemma, vec![1, 2]),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    let mut s = Solver::try_from((Config::default(), cnf.clone())).expect("failed to build");
+    match s.check_proof("d 1 2 3 4 5 0\n".as_bytes()) {
+        Err(ProofError::NoRefutation) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
@@ -65,7 +65,7 @@ fn setup() -> Solver {
     let cnf = CNFDescription {
         num_of_variables: 5,
         num_of_clauses: 6,
-        pathname: "".to_string(),
+        pathname: None,
     };
     let mut s = Solver::new(&Default::default(), &cnf);
     attach_clause(&mut s, &mkv![1, 2, -3]).activity(1.0);
@@ -0,0 +1,132 @@
+use splr::config::Config;
+use splr::counter::{Count, ModelCount};
+use splr::solver::{Certificate, Solver, SolverException};
+use splr::state::Budget;
+use splr::traits::*;
+use splr::types::ParseError;
+use std::convert::TryFrom;
+
+fn build(cnf: &str) -> Solver {
+    let mut s = Solver::from_reader(&Config::default(), cnf.as_bytes()).unwrap();
+    s.state.use_progress = false;
+    s
+}
+
+fn count(s: &mut Solver) -> Count {
+    match s.count_models() {
+        Ok(ModelCount::Exact(n)) => n,
+        res => panic!("{:?}", res),
+    }
+}
+
+#[test]
+fn count_independent_components() {
+    // 50 clauses `x | y` on disjoint vars have 3^50 models.
+    let cnf = (0..50)
+        .map(|i| vec![2 * i + 1, 2 * i + 2])
+        .collect::<Vec<Vec<i32>>>();
+    let mut s = Solver::try_from((Config::default(), cnf)).unwrap();
+    assert_eq!(count(&mut s).to_string(), "717897987691852588770249");
+}
+
+#[test]
+fn count_free_vars() {
+    let mut s = build("p cnf 100 1\n1 -1 0\n");
+    assert_eq!(count(&mut s).to_string(), "1267650600228229401496703205376");
+}
+
+#[test]
+fn count_unsatisfiable() {
+    let mut s = build("p cnf 3 4\n1 2 0\n-1 2 0\n1 -2 0\n-1 -2 0\n");
+    assert!(count(&mut s).is_zero());
+}
+
+#[test]
+fn count_after_solve() {
+    // `x1 -> x2 -> x3 -> x4` has 5 models.
+    let mut s = build("p cnf 4 3\n-1 2 0\n-2 3 0\n-3 4 0\n");
+    assert!(matches!(s.solve(), Ok(Certificate::SAT(_))));
+    assert_eq!(count(&mut s).to_string(), "5");
+    // the clauses are kept.
+    assert!(matches!(s.solve(), Ok(Certificate::SAT(_))));
+}
+
+#[test]
+fn count_projected_models() {
+    // exactly one of 1, 2 and 3 is true, and 4 is free; the projection on 1 and 4 hides
+    // the choice between 2 and 3.
+    let cnf = "c ind 1 4 0\np cnf 4 4\n1 2 3 0\n-1 -2 0\n-1 -3 0\n-2 -3 0\n";
+    let mut s = build(cnf);
+    assert_eq!(s.state.projection, vec![1, 4]);
+    assert_eq!(count(&mut s).to_string(), "4");
+    s.state.projection.clear();
+    assert_eq!(count(&mut s).to_string(), "6");
+}
+
+#[test]
+fn load_projection() {
+    // a comment which begins with `c ind` isn't a projection.
+    let s = build("c ind = projection\np cnf 2 1\n1 2 0\n");
+    assert!(s.state.projection.is_empty());
+    let cnf = "c ind 1 3 0\np cnf 2 1\n1 2 0\n";
+    assert!(matches!(
+        Solver::from_reader(&Config::default(), cnf.as_bytes()),
+        Err(ParseError::OutOfRangeLiteral {
+            line: 1,
+            column: 9,
+            literal: 3
+        })
+    ));
+    let config = Config {
+        use_lenient_parser: true,
+        ..Config::default()
+    };
+    let mut s = Solver::from_reader(&config, cnf.as_bytes()).unwrap();
+    s.state.use_progress = false;
+    assert_eq!(s.state.projection, vec![1, 3]);
+    assert_eq!(count(&mut s).to_string(), "4");
+}
+
+#[test]
+fn count_interrupted() {
+    let mut s = build("p cnf 3 1\n1 2 3 0\n");
+    s.interrupt_handle().interrupt();
+    assert!(matches!(
+        s.count_models(),
+        Err(SolverException::Interrupted)
+    ));
+    assert_eq!(count(&mut s).to_string(), "7");
+}
+
+#[test]
+fn count_deep_branches() {
+    // a single clause over many vars is counted by branching on each of them in turn.
+    let nv = 2000;
+    let mut s =
+        Solver::try_from((Config::default(), vec![(1..=nv).collect::<Vec<i32>>()])).unwrap();
+    s.state.use_progress = false;
+    let mut n = count(&mut s);
+    n += &Count::one();
+    let mut all = Count::one();
+    all <<= nv as usize;
+    assert_eq!(n, all);
+}
+
+#[test]
+fn count_out_of_budget() {
+    let mut s = build("p cnf 6 3\n1 2 3 0\n-1 4 5 0\n-4 -5 6 0\n");
+    s.state.budget_decision = 1;
+    assert_eq!(
+        s.count_models().unwrap(),
+        ModelCount::Unknown(Budget::Decision)
+    );
+    s.state.budget_decision = 0;
+    // the cache of counts is included in the memory budget.
+    s.state.budget_memory = 1;
+    assert_eq!(
+        s.count_models().unwrap(),
+        ModelCount::Unknown(Budget::Memory)
+    );
+    s.state.budget_memory = 0;
+    assert!(!count(&mut s).is_zero());
+}
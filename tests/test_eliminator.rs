@@ -21,7 +21,7 @@ fn check_occurs() {
     let cnf: CNFDescription = CNFDescription {
         num_of_variables: 10,
         num_of_clauses: 10,
-        pathname: "".to_string(),
+        pathname: None,
     };
     let mut s = Solver::new(&cfg, &cnf);
 
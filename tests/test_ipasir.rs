@@ -0,0 +1,139 @@
+use splr::ipasir::*;
+use std::ffi::CStr;
+use std::os::raw::{c_int, c_void};
+
+/// adds a pigeon hole problem (n + 1 pigeons, n holes)
+fn add_pigeon_hole(s: *mut c_void, n: i32) {
+    let lit = |p: i32, h: i32| p * n + h + 1;
+    unsafe {
+        for p in 0..=n {
+            for h in 0..n {
+                ipasir_add(s, lit(p, h));
+            }
+            ipasir_add(s, 0);
+        }
+        for h in 0..n {
+            for p in 0..=n {
+                for q in p + 1..=n {
+                    ipasir_add(s, -lit(p, h));
+                    ipasir_add(s, -lit(q, h));
+                    ipasir_add(s, 0);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn ipasir_incremental_solve() {
+    let s = ipasir_init();
+    unsafe {
+        ipasir_add(s, 1);
+        ipasir_add(s, 2);
+        ipasir_add(s, 0);
+        ipasir_assume(s, -1);
+        ipasir_assume(s, -2);
+        ipasir_assume(s, 3);
+        assert_eq!(ipasir_solve(s), 20);
+        assert_eq!(
+            (
+                ipasir_failed(s, -1),
+                ipasir_failed(s, -2),
+                ipasir_failed(s, 3)
+            ),
+            (1, 1, 0)
+        );
+        // assumptions are cleared, and 0 is ignored.
+        ipasir_assume(s, -1);
+        ipasir_assume(s, 0);
+        assert_eq!(ipasir_solve(s), 10);
+        assert_eq!((ipasir_val(s, 1), ipasir_val(s, -2)), (-1, 2));
+        ipasir_add(s, -2);
+        ipasir_add(s, 0);
+        assert_eq!(ipasir_solve(s), 10);
+        assert_eq!((ipasir_val(s, 1), ipasir_val(s, 2)), (1, -2));
+        ipasir_add(s, -1);
+        ipasir_add(s, 0);
+        assert_eq!(ipasir_solve(s), 20);
+        ipasir_release(s);
+        assert_eq!(
+            CStr::from_ptr(ipasir_signature()).to_str().unwrap(),
+            format!("splr-{}", env!("CARGO_PKG_VERSION"))
+        );
+    }
+}
+
+unsafe extern "C" fn count_learnt(data: *mut c_void, clause: *mut i32) {
+    let mut len = 0;
+    while *clause.add(len) != 0 {
+        len += 1;
+    }
+    let count = &mut *(data as *mut (usize, usize));
+    count.0 += 1;
+    count.1 = count.1.max(len);
+}
+
+unsafe extern "C" fn terminate_at_once(data: *mut c_void) -> c_int {
+    *(data as *mut usize) += 1;
+    1
+}
+
+#[test]
+fn ipasir_callbacks() {
+    let mut count: (usize, usize) = (0, 0);
+    let s = ipasir_init();
+    unsafe {
+        add_pigeon_hole(s, 6);
+        ipasir_set_learn(
+            s,
+            &mut count as *mut _ as *mut c_void,
+            4,
+            Some(count_learnt),
+        );
+        assert_eq!(ipasir_solve(s), 20);
+        ipasir_release(s);
+    }
+    assert!(0 < count.0 && count.1 <= 4);
+    let mut polled: usize = 0;
+    let s = ipasir_init();
+    unsafe {
+        add_pigeon_hole(s, 8);
+        ipasir_set_terminate(
+            s,
+            &mut polled as *mut _ as *mut c_void,
+            Some(terminate_at_once),
+        );
+        assert_eq!(ipasir_solve(s), 0);
+        // the solver stays usable.
+        ipasir_set_terminate(s, std::ptr::null_mut(), None);
+        ipasir_assume(s, 1);
+        ipasir_assume(s, 2);
+        assert_eq!(ipasir_solve(s), 20);
+        ipasir_release(s);
+    }
+    assert!(0 < polled);
+}
+
+#[test]
+fn ipasir_terminate_before_search() {
+    // a chain of implications is solved by elimination without search.
+    let mut polled: usize = 0;
+    let s = ipasir_init();
+    unsafe {
+        for i in 1..2000 {
+            ipasir_add(s, -i);
+            ipasir_add(s, i + 1);
+            ipasir_add(s, 0);
+        }
+        ipasir_set_terminate(
+            s,
+            &mut polled as *mut _ as *mut c_void,
+            Some(terminate_at_once),
+        );
+        assert_eq!(ipasir_solve(s), 0);
+        ipasir_set_terminate(s, std::ptr::null_mut(), None);
+        assert_eq!(ipasir_solve(s), 10);
+        ipasir_release(s);
+    }
+    assert!(0 < polled);
+}
@@ -0,0 +1,77 @@
+use splr::config::Config;
+use splr::solver::{Certificate, Solver};
+use splr::traits::*;
+use splr::types::*;
+
+fn load(cnf: &str, lenient: bool) -> Result<Solver, ParseError> {
+    let config = Config {
+        use_lenient_parser: lenient,
+        ..Config::default()
+    };
+    Solver::from_reader(&config, cnf.as_bytes())
+}
+
+#[test]
+fn parse_clauses_over_lines() {
+    let mut s = load("c comment\np cnf 3 2\n1 -2\n 3 0 -1 0\n", false).unwrap();
+    s.state.use_progress = false;
+    assert_eq!(s.solve().unwrap(), Certificate::SAT(vec![-1, -2, 3]));
+}
+
+#[test]
+fn parse_errors() {
+    match load("1 2 0\n", false) {
+        Err(ParseError::MissingHeader) => (),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    match load("c\np cnf x 1\n1 0\n", false) {
+        Err(ParseError::BadHeader { line: 2 }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    match load("p cnf 2 1\n1 -a 0\n", false) {
+        Err(ParseError::BadLiteral { line: 2, column: 3 }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    match load("p cnf 2 1\n1  -3 0\n", false) {
+        Err(ParseError::OutOfRangeLiteral {
+            line: 2,
+            column: 4,
+            literal: -3,
+        }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+    match load("p cnf 2 2\n1 -2 0\n", false) {
+        Err(ParseError::ClauseCountMismatch {
+            expected: 2,
+            found: 1,
+        }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|_| ())),
+    }
+}
+
+#[test]
+fn parse_leniently() {
+    let mut s = load("p cnf 2 3\n1 -3 0\n-1 0\n", true).unwrap();
+    s.state.use_progress = false;
+    assert_eq!(s.state.num_vars, 3);
+    match s.solve() {
+        Ok(Certificate::SAT(m)) => assert_eq!((m[0], m[2]), (-1, -3)),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn parse_models() {
+    let (nv, vec) = read_dimacs_integers("c\ns SATISFIABLE\nv 1 -2\nv 3 0\n".as_bytes()).unwrap();
+    assert_eq!((nv, vec), (None, vec![vec![1, -2, 3]]));
+    let (nv, vec) = read_dimacs_integers("p cnf 3 2\n1 -2 0 3".as_bytes()).unwrap();
+    assert_eq!((nv, vec), (Some(3), vec![vec![1, -2], vec![3]]));
+    match read_dimacs_integers("s UNSATISFIABLE\n".as_bytes()) {
+        Err(ParseError::NoModel { line: 1, .. }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    match read_dimacs_integers("v 1 x 0\n".as_bytes()) {
+        Err(ParseError::BadLiteral { line: 1, column: 5 }) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
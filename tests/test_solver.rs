@@ -0,0 +1,496 @@
+use splr::config::Config;
+use splr::eliminator::Eliminator;
+use splr::solver::{Certificate, Solver};
+use splr::state::{Budget, ProgressRecord, Stat};
+use splr::traits::*;
+use splr::types::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+macro_rules! mkv {
+    ($($x:expr),*) => {
+        match &[$($x),*] {
+            v => v.iter().map(|x| Lit::from_int(*x)).collect::<Vec<Lit>>(),
+        }
+    };
+}
+
+fn setup(nv: usize, clauses: &[Vec<Lit>]) -> Solver {
+    let cnf = CNFDescription {
+        num_of_variables: nv,
+        num_of_clauses: clauses.len(),
+        pathname: None,
+    };
+    let mut s = Solver::new(&Config::default(), &cnf);
+    s.state.use_progress = false;
+    for c in clauses {
+        assert!(s.add_unchecked_clause(&mut c.clone()).is_some());
+    }
+    s
+}
+
+fn failed_assumptions(s: &mut Solver, assumptions: &[Lit]) -> Vec<i32> {
+    match s.solve_with_assumptions(assumptions) {
+        Ok(Certificate::UNSATUnderAssumptions(mut v)) => {
+            v.sort();
+            v
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn failed_assumptions_by_propagation() {
+    let mut s = setup(4, &[mkv![-1, 3], mkv![-2, -3]]);
+    assert_eq!(failed_assumptions(&mut s, &mkv![4, 1, 2]), vec![1, 2]);
+    assert_eq!(failed_assumptions(&mut s, &mkv![2, 4, 1]), vec![1, 2]);
+}
+
+#[test]
+fn failed_assumptions_by_contradiction() {
+    let mut s = setup(3, &[mkv![1, 2, 3]]);
+    assert_eq!(failed_assumptions(&mut s, &mkv![2, 1, -2]), vec![-2, 2]);
+}
+
+#[test]
+fn failed_assumptions_by_fact() {
+    let mut s = setup(3, &[mkv![-1], mkv![1, 2, 3]]);
+    assert_eq!(failed_assumptions(&mut s, &mkv![2, 1]), vec![1]);
+}
+
+#[test]
+fn failed_assumptions_by_search() {
+    // a pigeon hole problem (3 pigeons, 2 holes) guarded by `7`, which needs conflict analysis.
+    let mut s = setup(
+        7,
+        &[
+            mkv![-7, 1, 2],
+            mkv![-7, 3, 4],
+            mkv![-7, 5, 6],
+            mkv![-1, -3],
+            mkv![-1, -5],
+            mkv![-3, -5],
+            mkv![-2, -4],
+            mkv![-2, -6],
+            mkv![-4, -6],
+        ],
+    );
+    assert_eq!(failed_assumptions(&mut s, &mkv![7]), vec![7]);
+    match s.solve() {
+        Ok(Certificate::SAT(v)) => assert_eq!(v[6], -7),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn unsat_without_assumptions() {
+    let mut s = setup(3, &[mkv![1, 2], mkv![-1, 2], mkv![1, -2], mkv![-1, -2]]);
+    match s.solve_with_assumptions(&mkv![3]) {
+        Ok(Certificate::UNSAT) => (),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+fn model(s: &mut Solver) -> Vec<i32> {
+    match s.solve() {
+        Ok(Certificate::SAT(v)) => v,
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn incremental_solve() {
+    let mut s = setup(3, &[mkv![1, 2], mkv![-1, 2], mkv![2, 3]]);
+    assert_eq!(model(&mut s)[1], 2);
+    assert!(s.add_unchecked_clause(&mut mkv![-2, 3]).is_some());
+    let m = model(&mut s);
+    assert_eq!(m[1..], [2, 3]);
+    s.add_unchecked_clause(&mut mkv![-3]);
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+#[test]
+fn incremental_solve_after_unsat() {
+    let mut s = setup(4, &[mkv![1, -3], mkv![-2, 1], mkv![1, 2, 3, 4]]);
+    s.add_unchecked_clause(&mut mkv![-1, -4]);
+    s.add_unchecked_clause(&mut mkv![3, 1]);
+    s.add_unchecked_clause(&mut mkv![4]);
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+    // the refutation holds for any clauses added later.
+    assert!(s.add_unchecked_clause(&mut mkv![-1, -1]).is_none());
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+#[test]
+fn incremental_solve_on_eliminated_vars() {
+    let mut s = setup(3, &[mkv![1, 2], mkv![-1, 3]]);
+    model(&mut s);
+    assert!(0 < s.state.num_eliminated_vars);
+    s.add_unchecked_clause(&mut mkv![-2]);
+    s.add_unchecked_clause(&mut mkv![-3]);
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+#[test]
+fn incremental_solve_restores_only_needed_vars() {
+    let mut s = setup(6, &[mkv![1, 2], mkv![-1, 3], mkv![4, 5], mkv![-4, 6]]);
+    model(&mut s);
+    let eliminated = s.state.num_eliminated_vars;
+    assert!(s.vars[1].is(Flag::ELIMINATED) && s.vars[4].is(Flag::ELIMINATED));
+    s.add_unchecked_clause(&mut mkv![-1]);
+    assert!(!s.vars[1].is(Flag::ELIMINATED) && s.vars[4].is(Flag::ELIMINATED));
+    assert_eq!(s.state.num_eliminated_vars, eliminated - 1);
+    let m = model(&mut s);
+    assert_eq!(m[..2], [-1, 2]);
+    s.add_unchecked_clause(&mut mkv![-2]);
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+#[test]
+fn incremental_solve_on_pure_literals() {
+    let mut s = setup(3, &[mkv![1, 2], mkv![1, -3]]);
+    assert_eq!(model(&mut s)[0], 1);
+    s.add_unchecked_clause(&mut mkv![-1]);
+    assert_eq!(model(&mut s), vec![-1, 2, -3]);
+}
+
+#[test]
+fn new_var_after_solve() {
+    let mut s = setup(3, &[mkv![1, 2], mkv![-1, 3]]);
+    model(&mut s);
+    assert_eq!(s.new_var(), 4);
+    s.add_unchecked_clause(&mut mkv![-4, -2]);
+    s.add_unchecked_clause(&mut mkv![4]);
+    assert_eq!(model(&mut s), vec![1, -2, 3, 4]);
+    s.add_unchecked_clause(&mut mkv![-1]);
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+/// a pigeon hole problem (n + 1 pigeons, n holes)
+fn pigeon_hole(n: i32) -> Solver {
+    let lit = |p: i32, h: i32| p * n + h + 1;
+    let mut clauses = Vec::new();
+    for p in 0..=n {
+        clauses.push(
+            (0..n)
+                .map(|h| Lit::from_int(lit(p, h)))
+                .collect::<Vec<Lit>>(),
+        );
+    }
+    for h in 0..n {
+        for p in 0..=n {
+            for q in p + 1..=n {
+                clauses.push(mkv![-lit(p, h), -lit(q, h)]);
+            }
+        }
+    }
+    setup(((n + 1) * n) as usize, &clauses)
+}
+
+#[test]
+fn budgets() {
+    let mut s = pigeon_hole(5);
+    s.state.use_elim = false;
+    s.state.budget_conflict = 10;
+    assert_eq!(s.solve().unwrap(), Certificate::Unknown(Budget::Conflict));
+    s.state.budget_conflict = 0;
+    s.state.budget_decision = 2;
+    assert_eq!(s.solve().unwrap(), Certificate::Unknown(Budget::Decision));
+    s.state.budget_decision = 0;
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+#[derive(Default)]
+struct Counter {
+    learnts: usize,
+    max_lbd: usize,
+    progresses: usize,
+}
+
+struct CountingObserver(Rc<RefCell<Counter>>);
+
+impl ObserverIF for CountingObserver {
+    fn on_learnt(&mut self, lits: &[Lit], lbd: usize) {
+        let mut c = self.0.borrow_mut();
+        assert!(!lits.is_empty() && lbd <= lits.len());
+        c.learnts += 1;
+        c.max_lbd = c.max_lbd.max(lbd);
+    }
+    fn on_progress(&mut self, _record: &ProgressRecord) {
+        self.0.borrow_mut().progresses += 1;
+    }
+}
+
+#[test]
+fn observer() {
+    let counter = Rc::new(RefCell::new(Counter::default()));
+    let mut s = pigeon_hole(5);
+    s.add_observer(Box::new(CountingObserver(counter.clone())));
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+    let c = counter.borrow();
+    // `Stat::Learnt` doesn't count unit clauses.
+    assert!(s.state.stats[Stat::Learnt] <= c.learnts);
+    assert!(1 < c.max_lbd);
+    assert!(0 < c.progresses);
+}
+
+#[test]
+fn preprocess_and_extend() {
+    let config = Config::from("tests/sample.cnf");
+    let mut s = Solver::build(&config).unwrap();
+    s.state.use_progress = false;
+    assert!(s.preprocess().unwrap());
+    assert!(0 < s.state.num_eliminated_vars);
+    let file = std::env::temp_dir().join(format!("splr-preprocess-{}.cnf", std::process::id()));
+    s.asgs.dump_cnf(&s.cdb, &s.state, &s.vars, &file).unwrap();
+    let mut reduced = Solver::build(&Config::from(&file)).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    reduced.state.use_progress = false;
+    let mut m = model(&mut reduced);
+    // a model of the simplified CNF isn't always a model of the original one.
+    let mut elim = Eliminator::new(s.state.num_vars);
+    elim.extend_reconstruction_stack(&s.elim.reconstruction_stack());
+    elim.extend_model(&mut m);
+    let mut checker = Solver::build(&config).unwrap();
+    assert!(checker.inject_assigmnent(&m).is_ok());
+    assert_eq!(checker.validate(), None);
+    // `solve` skips the preprocessing.
+    assert_eq!(s.state.stats[Stat::ExhaustiveElimination], 1);
+    model(&mut s);
+    assert_eq!(s.state.stats[Stat::Solve], 1);
+}
+
+/// an at-most-one constraint over vars `1..=n` as a theory.
+#[derive(Default)]
+struct AtMostOne {
+    n: usize,
+    /// true vars and their levels
+    trues: Vec<(VarId, usize)>,
+    rejected: Vec<Vec<Lit>>,
+}
+
+impl ExternalPropagatorIF for AtMostOne {
+    fn notify_assignment(&mut self, lits: &[Lit], level: usize) {
+        for l in lits {
+            if l.is_positive() && !self.trues.iter().any(|(vi, _)| *vi == l.vi()) {
+                self.trues.push((l.vi(), level));
+            }
+        }
+    }
+    fn notify_backtrack(&mut self, level: usize) {
+        self.trues.retain(|(_, lv)| *lv <= level);
+    }
+    fn propagate(&mut self) -> Vec<Lit> {
+        match self.trues.first() {
+            Some((vi, _)) => (1..=self.n)
+                .filter(|j| j != vi)
+                .map(|j| Lit::from_int(-(j as i32)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+    fn reason(&mut self, lit: Lit) -> Vec<Lit> {
+        let vi = self.trues.iter().find(|(vi, _)| *vi != lit.vi()).unwrap().0;
+        vec![lit, Lit::from_int(-(vi as i32))]
+    }
+    fn add_clause(&mut self) -> Option<Vec<Lit>> {
+        self.rejected.pop()
+    }
+    fn check_model(&mut self, model: &[i32]) -> bool {
+        let trues = model[..self.n].iter().filter(|l| 0 < **l).count();
+        assert!(trues <= 1);
+        true
+    }
+}
+
+#[test]
+fn external_propagator() {
+    let mut s = setup(6, &[mkv![1, 2, 3, 4], mkv![-5, 1, 2], mkv![5, 6]]);
+    s.connect_propagator(Box::new(AtMostOne {
+        n: 4,
+        ..AtMostOne::default()
+    }));
+    for vi in 1..=4 {
+        s.add_observed_var(vi);
+    }
+    let mut count = 0;
+    while let Ok(Certificate::SAT(v)) = s.solve() {
+        assert_eq!(v[..4].iter().filter(|l| 0 < **l).count(), 1);
+        count += 1;
+        s.add_unchecked_clause(&mut v.iter().map(|l| Lit::from_int(-l)).collect::<Vec<Lit>>());
+    }
+    // 3 models of 5 and 6 for each of 1 and 2, and one for each of 3 and 4
+    assert_eq!(count, 8);
+    assert!(s.disconnect_propagator().is_some());
+    // (1 | 2) and (3 | 4) can't be true under the theory.
+    let mut s = setup(4, &[mkv![1, 2], mkv![3, 4]]);
+    s.connect_propagator(Box::new(AtMostOne {
+        n: 4,
+        ..AtMostOne::default()
+    }));
+    for vi in 1..=4 {
+        s.add_observed_var(vi);
+    }
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+/// a theory which is checked only on complete models: var 1 must be false.
+struct LazyCheck(Vec<Vec<Lit>>);
+
+impl ExternalPropagatorIF for LazyCheck {
+    fn reason(&mut self, _lit: Lit) -> Vec<Lit> {
+        unreachable!()
+    }
+    fn add_clause(&mut self) -> Option<Vec<Lit>> {
+        self.0.pop()
+    }
+    fn check_model(&mut self, model: &[i32]) -> bool {
+        if 0 < model[0] {
+            self.0.push(mkv![-1]);
+            return false;
+        }
+        true
+    }
+}
+
+#[test]
+fn external_propagator_checks_models() {
+    let mut s = setup(3, &[mkv![1, 2], mkv![1, 3], mkv![-2, -3]]);
+    s.connect_propagator(Box::new(LazyCheck(Vec::new())));
+    s.add_observed_var(1);
+    assert_eq!(s.solve().unwrap(), Certificate::UNSAT);
+}
+
+/// a theory where 2 implies -1, and 4 is false; it propagates them after deciding 2.
+#[derive(Default)]
+struct LateFacts {
+    decided: bool,
+    trues: Vec<VarId>,
+    asked: Rc<RefCell<Vec<Lit>>>,
+}
+
+impl ExternalPropagatorIF for LateFacts {
+    fn notify_assignment(&mut self, lits: &[Lit], _level: usize) {
+        self.trues
+            .extend(lits.iter().filter(|l| l.is_positive()).map(|l| l.vi()));
+    }
+    fn notify_backtrack(&mut self, _level: usize) {
+        self.trues.clear();
+    }
+    fn decide(&mut self) -> Option<Lit> {
+        let first = !self.decided;
+        self.decided = true;
+        first.then(|| Lit::from_int(2))
+    }
+    fn propagate(&mut self) -> Vec<Lit> {
+        if self.trues.contains(&2) {
+            mkv![-1, -4]
+        } else {
+            Vec::new()
+        }
+    }
+    fn reason(&mut self, lit: Lit) -> Vec<Lit> {
+        self.asked.borrow_mut().push(lit);
+        if lit.vi() == 1 {
+            mkv![-1, -2]
+        } else {
+            Vec::new()
+        }
+    }
+    fn check_model(&mut self, model: &[i32]) -> bool {
+        assert!(model[3] < 0 && (model[0] < 0 || model[1] < 0));
+        true
+    }
+}
+
+#[test]
+fn external_propagator_explains_lazily() {
+    let mut s = setup(4, &[mkv![1, 4, 3], mkv![1, 4, -3]]);
+    let asked = Rc::new(RefCell::new(Vec::new()));
+    s.connect_propagator(Box::new(LateFacts {
+        asked: asked.clone(),
+        ..LateFacts::default()
+    }));
+    for vi in 1..=4 {
+        s.add_observed_var(vi);
+    }
+    assert!(matches!(s.solve(), Ok(Certificate::SAT(v)) if v[0] == 1 && v[3] == -4));
+    // -1 is the first UIP, so only -4 is explained at the conflict, and it's kept as a fact.
+    assert_eq!(asked.borrow()[0], Lit::from_int(-4));
+    assert_eq!(asked.borrow().iter().filter(|l| l.vi() == 4).count(), 1);
+    assert_eq!(s.vars[4].assign, FALSE);
+    assert_eq!(s.vars[4].level, 0);
+}
+
+#[test]
+fn freeze_and_melt() {
+    let mut s = setup(3, &[mkv![1, 2], mkv![-1, 3]]);
+    s.freeze(1);
+    s.freeze(1);
+    s.melt(1);
+    model(&mut s);
+    assert!(!s.vars[1].is(Flag::ELIMINATED));
+    // assumptions on a frozen var work after solving.
+    assert!(matches!(
+        s.solve_with_assumptions(&mkv![1]),
+        Ok(Certificate::SAT(v)) if v[0] == 1
+    ));
+    s.melt(1);
+    assert!(!s.vars[1].is(Flag::FROZEN));
+    s.add_unchecked_clause(&mut mkv![-2]);
+    assert_eq!(model(&mut s), vec![1, -2, 3]);
+}
+
+#[test]
+fn models_with_projection() {
+    let mut s = setup(4, &[mkv![1, 2], mkv![-1, 3], mkv![3, 4]]);
+    let mut models = s.models(&[1, 3]).unwrap();
+    let projected = models.by_ref().collect::<Vec<Vec<i32>>>();
+    assert!(models.is_complete());
+    drop(models);
+    for m in &projected {
+        assert_eq!(m.len(), 4);
+        assert!((0 < m[0] || 0 < m[1]) && (m[0] < 0 || 0 < m[2]) && (0 < m[2] || 0 < m[3]));
+    }
+    let mut keys = projected.iter().map(|m| (m[0], m[2])).collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys.len(), projected.len());
+    // 1 and 3, -1 and 3, and -1 and -3
+    assert_eq!(projected.len(), 3);
+    assert!(s.vars[1..=4].iter().all(|v| !v.is(Flag::FROZEN)));
+    // the blocking clauses are removed after the iteration.
+    assert!(matches!(s.solve(), Ok(Certificate::SAT(m)) if m.len() == 4));
+    assert_eq!(s.models(&[1, 3]).unwrap().count(), 3);
+    assert_eq!(s.models(&[]).unwrap().count(), 7);
+    assert!(s.models(&[0]).is_err());
+    assert!(s.models(&[5]).is_err());
+    // the var hidden for the iteration is given to the user as a new var.
+    assert_eq!(s.new_var(), 5);
+    s.add_unchecked_clause(&mut mkv![-5, -1]);
+    assert_eq!(s.models(&[1, 5]).unwrap().count(), 3);
+    assert!(matches!(s.solve(), Ok(Certificate::SAT(m)) if m.len() == 5));
+}
+
+#[test]
+fn models_forced_by_learnt_facts() {
+    // 1 is found by conflicts, and 3 then fixes the projected model at level zero.
+    let mut s = setup(3, &[mkv![1, 2], mkv![1, -2], mkv![-1, 3]]);
+    for _ in 0..3 {
+        assert_eq!(s.models(&[1, 3]).unwrap().count(), 1);
+    }
+    assert_eq!(s.models(&[]).unwrap().count(), 2);
+}
+
+#[test]
+fn models_cut_short() {
+    let mut s = pigeon_hole(5);
+    s.state.budget_conflict = 10;
+    let mut models = s.models(&[]).unwrap();
+    assert_eq!(models.next(), None);
+    assert!(matches!(
+        models.result(),
+        Some(Ok(Certificate::Unknown(Budget::Conflict)))
+    ));
+    assert!(!models.is_complete());
+}